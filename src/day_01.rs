@@ -12,7 +12,7 @@ pub fn solve_star_one(input: &str) -> u32 {
 
 /// find the three elfes with the most calories and returns the sum of the calories
 pub fn solve_star_two(input: &str) -> u32 {
-    let mut elfes = Elf::build(&input);
+    let mut elfes = Elf::build(input);
 
     elfes.sort_by_key(|elf| Reverse(elf.carrying_total()));
 
@@ -95,13 +95,13 @@ mod tests_day_01 {
     }
 
     fn star_two_base_test(input: &str, expected: u32) {
-        let result = solve_star_two(&input);
+        let result = solve_star_two(input);
         assert_eq!(result, expected);
     }
 
     #[test]
     fn star_one_example_test() {
-        star_one_base_test(&EXAMPLE, 24000);
+        star_one_base_test(EXAMPLE, 24000);
     }
 
     #[test]
@@ -112,7 +112,7 @@ mod tests_day_01 {
 
     #[test]
     fn star_two_example() {
-        star_two_base_test(&EXAMPLE, 45000); // 68292 is the right answer
+        star_two_base_test(EXAMPLE, 45000); // 68292 is the right answer
     }
 
     #[test]
@@ -9,7 +9,7 @@ pub fn solve_stars(input: &str, strat: Option<bool>) -> u32 {
             panic!("There are to less values in the line {}", index + 1)
         }
 
-        scores.push(evaluate_round(&parts[1], &parts[0], strat));
+        scores.push(evaluate_round(parts[1], parts[0], strat));
     }
 
     scores.iter().sum()
@@ -37,7 +37,7 @@ fn choose_tool_score(opponent: &str, win_condition: &str) -> u32 {
     match win_condition {
         // lose
         "X" => {
-            let score = evaluate_tool_score(&opponent);
+            let score = evaluate_tool_score(opponent);
             if score == 1 {
                 3
             } else {
@@ -45,10 +45,10 @@ fn choose_tool_score(opponent: &str, win_condition: &str) -> u32 {
             }
         }
         // draw
-        "Y" => evaluate_tool_score(&opponent),
+        "Y" => evaluate_tool_score(opponent),
         // win
         "Z" => {
-            let score = evaluate_tool_score(&opponent);
+            let score = evaluate_tool_score(opponent);
             if score == 3 {
                 1
             } else {
@@ -64,10 +64,10 @@ fn choose_tool_score(opponent: &str, win_condition: &str) -> u32 {
 fn evaluate_round(me: &str, opponent: &str, strat: Option<bool>) -> u32 {
     let strat = strat.unwrap_or(false);
     let own_score = match strat {
-        true => choose_tool_score(&opponent, &me),
-        _ => evaluate_tool_score(&me),
+        true => choose_tool_score(opponent, me),
+        _ => evaluate_tool_score(me),
     };
-    let opp_score = evaluate_tool_score(&opponent);
+    let opp_score = evaluate_tool_score(opponent);
 
     // draw
     if own_score == opp_score {
@@ -97,7 +97,7 @@ mod tests_day_02 {
 A Y
 B X
 C Z";
-        let result = solve_stars(&input, None);
+        let result = solve_stars(input, None);
         assert_eq!(result, 15);
     }
 
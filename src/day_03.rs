@@ -3,7 +3,7 @@
 /// Solves star one
 /// Sums the priorities
 pub fn solve_star_one(input: &str) -> u32 {
-    input.lines().map(|l| get_priority_rucksack(l)).sum()
+    input.lines().map(get_priority_rucksack).sum()
 }
 
 /// Solves star two
@@ -13,7 +13,7 @@ pub fn solve_star_two(input: &str) -> u32 {
     let lines: Vec<&str> = input.lines().collect();
     let mut sum: u32 = 0;
     // all groups have to consist of the rucksacks
-    if lines.len() % 3 != 0 {
+    if !lines.len().is_multiple_of(3) {
         panic!("The input lines have to be a multiple of 3");
     }
     // create chunks that are same as a group
@@ -36,10 +36,10 @@ pub fn solve_star_two(input: &str) -> u32 {
 fn get_priority_item(letter: &char) -> u8 {
     // b'A' -> 65, b'Z' -> 90, b'a' -> 97, b'z' -> 122
     let value = *letter as u8;
-    if value >= b'a' && value <= b'z' {
+    if value.is_ascii_lowercase() {
         // Lowercase letters: 'a' (97) -> 1, ..., 'z' (122) -> 26
         value - b'a' + 1
-    } else if value >= b'A' && value <= b'Z' {
+    } else if value.is_ascii_uppercase() {
         // Uppercase letters: 'A' (65) -> 27, ..., 'Z' (90) -> 52
         value - b'A' + 27
     } else {
@@ -16,12 +16,12 @@ fn solve(input: &str, handle: fn(&Range, &Range) -> bool) -> usize {
 
 /// Solves the first star of day 4
 pub fn solve_star_one(input: &str) -> usize {
-    solve(&input, contains)
+    solve(input, contains)
 }
 
 /// Solves the second star of day 4
 pub fn solve_star_two(input: &str) -> usize {
-    solve(&input, overlap)
+    solve(input, overlap)
 }
 
 /// Returns true if Range b is fully in Range a
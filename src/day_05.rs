@@ -9,7 +9,7 @@ pub fn solve_star_two(input: &str) -> String {
 }
 
 fn solve(input: &str, crane_9001: bool) -> String {
-    let mut ship = Ship::build(&input, crane_9001);
+    let mut ship = Ship::build(input, crane_9001);
     ship.run_orders();
 
     let result: String = ship
@@ -181,7 +181,7 @@ move 1 from 1 to 2";
 
     #[test]
     fn exemple_execute_first_order() {
-        let mut ship = Ship::build(&EXAMPLE, false);
+        let mut ship = Ship::build(EXAMPLE, false);
         let line = ship.orders[0].clone();
 
         let result: Vec<Vec<char>> = vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']];
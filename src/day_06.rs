@@ -1,9 +1,10 @@
 #![doc = include_str!("descriptions/day_06.md")]
 
 use std::collections::HashSet;
+use std::io::{self, Read};
 
 pub fn solve_star_one(input: &str) -> Option<usize> {
-    find_marker(input, 4 as usize)
+    find_marker(input, 4_usize)
 }
 
 pub fn solve_star_two(input: &str) -> Option<usize> {
@@ -21,6 +22,131 @@ fn find_marker(input: &str, size: usize) -> Option<usize> {
     None
 }
 
+/// The two kinds of markers the device is looking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+impl MarkerKind {
+    /// the number of distinct characters that make up the marker
+    pub fn size(&self) -> usize {
+        match self {
+            MarkerKind::StartOfPacket => 4,
+            MarkerKind::StartOfMessage => 14,
+        }
+    }
+}
+
+/// A marker found in the datastream.
+/// The `offset` is the number of characters processed when the marker was detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub offset: usize,
+}
+
+/// Decodes a datastream chunk by chunk from any `Read`.
+///
+/// The decoder is an iterator that yields every marker as soon as it is found,
+/// so the signal never has to be buffered as a whole.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_06::{Marker, MarkerKind, StreamDecoder};
+///
+/// let signal = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+/// let markers: Vec<Marker> = StreamDecoder::new(signal.as_bytes())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(markers[0], Marker { kind: MarkerKind::StartOfPacket, offset: 7 });
+/// assert_eq!(markers[1], Marker { kind: MarkerKind::StartOfMessage, offset: 19 });
+/// ```
+pub struct StreamDecoder<R: Read> {
+    reader: R,
+    chunk: [u8; 4096],
+    chunk_len: usize,
+    chunk_pos: usize,
+    /// the number of bytes processed so far
+    processed: usize,
+    /// the position (1-based) where every byte was seen the last time
+    last_seen: [usize; 256],
+    /// the start of the current window with only distinct characters
+    window_start: usize,
+    /// the markers that haven't been found yet
+    pending: Vec<MarkerKind>,
+}
+
+impl<R: Read> StreamDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chunk: [0; 4096],
+            chunk_len: 0,
+            chunk_pos: 0,
+            processed: 0,
+            last_seen: [0; 256],
+            window_start: 0,
+            pending: vec![MarkerKind::StartOfPacket, MarkerKind::StartOfMessage],
+        }
+    }
+
+    /// the number of bytes consumed from the reader so far
+    pub fn processed(&self) -> usize {
+        self.processed
+    }
+
+    /// Returns the next byte of the stream or `None` at the end of the stream
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.chunk_pos == self.chunk_len {
+            self.chunk_len = loop {
+                match self.reader.read(&mut self.chunk) {
+                    Ok(len) => break len,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            self.chunk_pos = 0;
+            if self.chunk_len == 0 {
+                return Ok(None);
+            }
+        }
+        let byte = self.chunk[self.chunk_pos];
+        self.chunk_pos += 1;
+        Ok(Some(byte))
+    }
+}
+
+impl<R: Read> Iterator for StreamDecoder<R> {
+    type Item = io::Result<Marker>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.pending.is_empty() {
+            let byte = match self.next_byte() {
+                Ok(Some(byte)) => byte,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            self.processed += 1;
+
+            // a repeated byte inside the window moves the start of the window behind it
+            let last = self.last_seen[byte as usize];
+            self.window_start = self.window_start.max(last);
+            self.last_seen[byte as usize] = self.processed;
+
+            let distinct = self.processed - self.window_start;
+            if let Some(index) = self.pending.iter().position(|k| distinct >= k.size()) {
+                let kind = self.pending.remove(index);
+                return Some(Ok(Marker {
+                    kind,
+                    offset: self.processed,
+                }));
+            }
+        }
+        None
+    }
+}
+
 
 #[cfg(test)]
 mod tests_day_06 {
@@ -32,7 +158,7 @@ mod tests_day_06 {
     fn example_star_one() {
         let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
 
-        let result = solve_star_one(&input);
+        let result = solve_star_one(input);
         assert_eq!(result, Some(7), "Expected 7 bit got {:?}", result);
     }
 
@@ -40,7 +166,7 @@ mod tests_day_06 {
     fn example_star_two() {
         let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
 
-        let result = solve_star_two(&input);
+        let result = solve_star_two(input);
         assert_eq!(result, Some(19), "Expected 19 bit got {:?}", result);
     }
     
@@ -59,4 +185,47 @@ mod tests_day_06 {
         let result = solve_star_two(&input);
         assert_eq!(result, Some(2851), "Expected 2851 bit got {:?}", result); 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn stream_decoder_examples() {
+        let examples = [
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ];
+        for (input, packet, message) in examples {
+            let markers: Vec<Marker> = StreamDecoder::new(input.as_bytes())
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(
+                markers,
+                vec![
+                    Marker {
+                        kind: MarkerKind::StartOfPacket,
+                        offset: packet
+                    },
+                    Marker {
+                        kind: MarkerKind::StartOfMessage,
+                        offset: message
+                    },
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn stream_decoder_input() {
+        let file = std::fs::File::open("src/test_files/day_06.txt").unwrap();
+        let markers: Vec<Marker> = StreamDecoder::new(file).collect::<Result<_, _>>().unwrap();
+        assert_eq!(markers[0].offset, 1794);
+        assert_eq!(markers[1].offset, 2851);
+    }
+
+    #[test]
+    fn stream_decoder_without_marker() {
+        let mut decoder = StreamDecoder::new("aabbaabb".as_bytes());
+        assert!(decoder.next().is_none());
+        assert_eq!(decoder.processed(), 8);
+    }
+}
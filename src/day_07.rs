@@ -1,96 +1,1041 @@
 #![doc = include_str!("descriptions/day_07.md")]
 
-use std::{collections::HashMap, vec};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
-pub fn solve_star_one(input: &str) -> u32 {
-    let sys = handle_file_system(&input);
-    let max: u32 = 100000;
-    let sum: u32 = sys
+use crate::rng::Rng;
+
+pub fn solve_star_one(input: &str) -> u64 {
+    let sys = handle_file_system(input).expect("The terminal output is inconsistent");
+    let max: u64 = 100000;
+    let sum: u64 = sys
+        .dir_sizes()
         .iter()
-        .map(|(_, &size)| if size <= max { size } else { 0 })
+        .map(|&(_, size)| if size <= max { size } else { 0 })
         .sum();
     sum
 }
 
-pub fn solve_star_two(input: &str) -> u32 {
-    let needed: i32 = 30_000_000;
-    let total_space: i32 = 70_000_000;
-    let sys = handle_file_system(input); // Assume this returns a HashMap<String, u32> or similar
-    let least_freed = needed - (total_space - *sys.get("/").unwrap() as i32);
+pub fn solve_star_two(input: &str) -> u64 {
+    let sys = handle_file_system(input).expect("The terminal output is inconsistent");
+    let plan = DeletionPlanner::default()
+        .best_single(&sys)
+        .expect("The update doesn't fit on the disk");
+
+    // nothing has to be deleted if there is already enough space
+    plan.map_or(0, |deletion| deletion.freed)
+}
 
-    if least_freed < 0 {
-        panic!("The calculation of the needed space failed - maybe there is already enought space for the update?")
+/// Rebuilds the file system from the terminal output
+pub fn handle_file_system(input: &str) -> Result<FileSystem, TranscriptError> {
+    FileSystem::from_str(input)
+}
+
+/// Index of a node in the arena of a `FileSystem`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// A node of the file system, either a directory or a plain file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Dir {
+        name: String,
+        parent: Option<NodeId>,
+        children: Vec<NodeId>,
+    },
+    File {
+        name: String,
+        size: u64,
+        parent: NodeId,
+    },
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Dir { name, .. } | Node::File { name, .. } => name,
+        }
     }
-    let least_freed = least_freed as u32;
 
-    // find the smalest possible folder to delete for the update
-    let big_enough = sys
-        .iter()
-        .filter(|(_, &size)| size > least_freed)
-        .map(|(_, &size)| size)
-        .min();
+    /// the directory that contains this node, `None` for the root
+    pub fn parent(&self) -> Option<NodeId> {
+        match self {
+            Node::Dir { parent, .. } => *parent,
+            Node::File { parent, .. } => Some(*parent),
+        }
+    }
 
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Node::Dir { .. })
+    }
 
-    match big_enough {
-        Some(size) => size,
-        None => panic!("There is something wrong!"),
+    /// the children of a directory, files haven't any
+    pub fn children(&self) -> &[NodeId] {
+        match self {
+            Node::Dir { children, .. } => children,
+            Node::File { .. } => &[],
+        }
     }
 }
 
-fn handle_file_system(input: &str) -> HashMap<String, u32> {
-    let mut current_pos: Vec<&str> = vec!["/"];
-    let mut file_system: HashMap<String, u32> = HashMap::new();
-    for line in input.lines() {
-        // handle the commands
+/// Errors found while rebuilding the file system from a terminal output.
+/// The `line` is the 1-based line number in the transcript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptError {
+    /// `cd` into a directory that was never listed
+    UnknownDirectory { line: usize, name: String },
+    /// `cd` into a file
+    NotADirectory { line: usize, name: String },
+    /// `cd ..` while already in `/`
+    AboveRoot { line: usize },
+    /// the same name shows up twice in one `ls` output
+    DuplicateEntry { line: usize, name: String },
+    /// a later `ls` output contradicts an earlier one
+    ConflictingEntry { line: usize, name: String },
+    /// output that doesn't belong to a `ls`
+    UnexpectedOutput { line: usize },
+    /// neither a known command nor a valid `ls` output
+    InvalidLine { line: usize },
+}
 
-        if line.starts_with("$ ls") || line.starts_with("dir") {
-            // these cases haven't any impact
-            continue;
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::UnknownDirectory { line, name } => {
+                write!(f, "line {line}: cd into the unknown directory '{name}'")
+            }
+            TranscriptError::NotADirectory { line, name } => {
+                write!(f, "line {line}: '{name}' is a file, not a directory")
+            }
+            TranscriptError::AboveRoot { line } => {
+                write!(f, "line {line}: cd .. from the outermost directory")
+            }
+            TranscriptError::DuplicateEntry { line, name } => {
+                write!(f, "line {line}: '{name}' is listed twice")
+            }
+            TranscriptError::ConflictingEntry { line, name } => {
+                write!(f, "line {line}: '{name}' differs from an earlier listing")
+            }
+            TranscriptError::UnexpectedOutput { line } => {
+                write!(f, "line {line}: output without a preceding ls")
+            }
+            TranscriptError::InvalidLine { line } => write!(f, "line {line}: invalid line"),
+        }
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+/// The file system of the device, stored as an arena of nodes.
+/// The root directory `/` is always the first node.
+///
+/// Two file systems are equal if they have the same tree, with the same names, sizes
+/// and order of the children, no matter in which order the nodes were added.
+#[derive(Debug, Clone)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl PartialEq for FileSystem {
+    fn eq(&self, other: &Self) -> bool {
+        // an explicit stack, deep trees would overflow the call stack
+        let mut stack = vec![(self.root(), other.root())];
+        while let Some((dir, other_dir)) = stack.pop() {
+            let children = self.node(dir).children();
+            let other_children = other.node(other_dir).children();
+            if children.len() != other_children.len() {
+                return false;
+            }
+            for (&child, &other_child) in children.iter().zip(other_children) {
+                match (self.node(child), other.node(other_child)) {
+                    (
+                        Node::Dir { name, .. },
+                        Node::Dir {
+                            name: other_name, ..
+                        },
+                    ) if name == other_name => {
+                        stack.push((child, other_child));
+                    }
+                    (
+                        Node::File { name, size, .. },
+                        Node::File {
+                            name: other_name,
+                            size: other_size,
+                            ..
+                        },
+                    ) if name == other_name && size == other_size => {}
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Eq for FileSystem {}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    /// Creates a file system with only the empty root directory
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node::Dir {
+                name: String::from("/"),
+                parent: None,
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// the number of nodes including the root
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// a file system always contains the root, so it is empty without any other node
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// Looks up the child with the given name in a directory
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.node(dir)
+            .children()
+            .iter()
+            .copied()
+            .find(|&child| self.node(child).name() == name)
+    }
+
+    /// Adds a directory to the given directory and returns the new node,
+    /// `None` if the parent is a file
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> Option<NodeId> {
+        self.push_node(
+            parent,
+            Node::Dir {
+                name: name.to_string(),
+                parent: Some(parent),
+                children: Vec::new(),
+            },
+        )
+    }
+
+    /// Adds a file to the given directory and returns the new node,
+    /// `None` if the parent is a file
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: u64) -> Option<NodeId> {
+        self.push_node(
+            parent,
+            Node::File {
+                name: name.to_string(),
+                size,
+                parent,
+            },
+        )
+    }
+
+    fn push_node(&mut self, parent: NodeId, node: Node) -> Option<NodeId> {
+        let id = NodeId(self.nodes.len());
+        // only directories can contain other nodes
+        let Node::Dir { children, .. } = &mut self.nodes[parent.0] else {
+            return None;
+        };
+        children.push(id);
+        self.nodes.push(node);
+        Some(id)
+    }
+
+    /// Finds a node by its absolute path like `/a/e/i`
+    pub fn find(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|part| !part.is_empty())
+            .try_fold(self.root(), |dir, name| self.child(dir, name))
+    }
+
+    /// Returns the absolute path of a node
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.node(current).parent() {
+            names.push(self.node(current).name());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    /// The number of directories between the root and a node, the root has a depth of 0
+    pub fn depth(&self, id: NodeId) -> usize {
+        let mut depth = 0;
+        let mut current = id;
+        while let Some(parent) = self.node(current).parent() {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+
+    /// Walks depth first through the whole file system, starting with the root
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            fs: self,
+            stack: vec![self.root()],
+        }
+    }
+
+    /// The depth of every node by its index, see `FileSystem::depth`
+    fn depths(&self) -> Vec<usize> {
+        let mut depths = vec![0; self.nodes.len()];
+        // a child is always added after its parent
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent() {
+                depths[index] = depths[parent.0] + 1;
+            }
+        }
+        depths
+    }
+
+    /// The total size of a node, for directories including everything in it
+    pub fn du(&self, id: NodeId) -> u64 {
+        // an explicit stack, deep trees would overflow the call stack
+        let mut stack = vec![id];
+        let mut total = 0;
+        while let Some(id) = stack.pop() {
+            match self.node(id) {
+                Node::File { size, .. } => total += size,
+                Node::Dir { children, .. } => stack.extend(children),
+            }
+        }
+        total
+    }
+
+    /// The total sizes of all directories, calculated in one pass
+    pub fn dir_sizes(&self) -> Vec<(NodeId, u64)> {
+        let mut sizes = vec![0; self.nodes.len()];
+        // a child is always added after its parent, so going backwards
+        // every node is complete before it is added to its parent
+        for (index, node) in self.nodes.iter().enumerate().rev() {
+            if let Node::File { size, .. } = node {
+                sizes[index] = *size;
+            }
+            if let Some(parent) = node.parent() {
+                sizes[parent.0] += sizes[index];
+            }
+        }
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_dir())
+            .map(|(index, _)| (NodeId(index), sizes[index]))
+            .collect()
+    }
+}
+
+/// Depth first iterator over a `FileSystem`, see `FileSystem::walk`
+pub struct Walk<'a> {
+    fs: &'a FileSystem,
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.stack.pop()?;
+        // push in reverse, so the children come out in the listed order
+        self.stack
+            .extend(self.fs.node(id).children().iter().rev().copied());
+        Some(id)
+    }
+}
+
+impl FromStr for FileSystem {
+    type Err = TranscriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fs = FileSystem::new();
+        let mut current = fs.root();
+        // the names of the current `ls` output, `None` if we aren't in one
+        let mut listing: Option<HashSet<&str>> = None;
+
+        for (index, line) in s.lines().enumerate() {
+            let line_nr = index + 1;
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+
+            // handle the commands
+            if let Some(command) = line.strip_prefix("$ ") {
+                listing = None;
+                match command.split_whitespace().collect::<Vec<&str>>()[..] {
+                    ["ls"] => listing = Some(HashSet::new()),
+                    ["cd", "/"] => current = fs.root(),
+                    ["cd", ".."] => {
+                        current = fs
+                            .node(current)
+                            .parent()
+                            .ok_or(TranscriptError::AboveRoot { line: line_nr })?;
+                    }
+                    ["cd", name] => {
+                        let child = fs.child(current, name).ok_or_else(|| {
+                            TranscriptError::UnknownDirectory {
+                                line: line_nr,
+                                name: name.to_string(),
+                            }
+                        })?;
+                        if !fs.node(child).is_dir() {
+                            return Err(TranscriptError::NotADirectory {
+                                line: line_nr,
+                                name: name.to_string(),
+                            });
+                        }
+                        current = child;
+                    }
+                    _ => return Err(TranscriptError::InvalidLine { line: line_nr }),
+                }
+                continue;
+            }
+
+            // handle the output of `ls`
+            let Some(names) = listing.as_mut() else {
+                return Err(TranscriptError::UnexpectedOutput { line: line_nr });
+            };
+            let (info, name) = line
+                .split_once(' ')
+                .ok_or(TranscriptError::InvalidLine { line: line_nr })?;
+            let size = match info {
+                "dir" => None,
+                size => Some(
+                    size.parse::<u64>()
+                        .map_err(|_| TranscriptError::InvalidLine { line: line_nr })?,
+                ),
+            };
+
+            if !names.insert(name) {
+                return Err(TranscriptError::DuplicateEntry {
+                    line: line_nr,
+                    name: name.to_string(),
+                });
+            }
+
+            // a directory can be listed more than once, but it has to stay the same
+            match (fs.child(current, name).map(|id| fs.node(id)), size) {
+                // `current` is always a directory, `cd` never goes into a file
+                (None, None) => {
+                    fs.add_dir(current, name);
+                }
+                (None, Some(size)) => {
+                    fs.add_file(current, name, size);
+                }
+                (Some(Node::Dir { .. }), None) => {}
+                (Some(Node::File { size: known, .. }), Some(size)) if *known == size => {}
+                (Some(_), _) => {
+                    return Err(TranscriptError::ConflictingEntry {
+                        line: line_nr,
+                        name: name.to_string(),
+                    })
+                }
+            }
+        }
+        Ok(fs)
+    }
+}
+
+/// Directories chosen to be deleted and the space that frees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deletion {
+    pub dirs: Vec<NodeId>,
+    pub freed: u64,
+}
+
+/// Errors that make it impossible to plan a deletion
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    /// the files take more space than the disk has
+    UsedExceedsDisk { used: u64, disk_size: u64 },
+    /// even an empty disk hasn't enough space
+    RequiredExceedsDisk { required: u64, disk_size: u64 },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::UsedExceedsDisk { used, disk_size } => {
+                write!(f, "{used} used on a disk with only {disk_size}")
+            }
+            PlanError::RequiredExceedsDisk {
+                required,
+                disk_size,
+            } => write!(f, "{required} required on a disk with only {disk_size}"),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+/// Plans which directories to delete to get the required space on the disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeletionPlanner {
+    pub disk_size: u64,
+    pub required: u64,
+}
+
+impl Default for DeletionPlanner {
+    /// the disk and the update of the puzzle
+    fn default() -> Self {
+        Self::new(70_000_000, 30_000_000)
+    }
+}
+
+impl DeletionPlanner {
+    pub fn new(disk_size: u64, required: u64) -> Self {
+        Self {
+            disk_size,
+            required,
+        }
+    }
+
+    /// The space that has to be freed, 0 if there is already enough
+    pub fn to_free(&self, fs: &FileSystem) -> Result<u64, PlanError> {
+        let used = fs.du(fs.root());
+        if used > self.disk_size {
+            return Err(PlanError::UsedExceedsDisk {
+                used,
+                disk_size: self.disk_size,
+            });
+        }
+        if self.required > self.disk_size {
+            return Err(PlanError::RequiredExceedsDisk {
+                required: self.required,
+                disk_size: self.disk_size,
+            });
         }
+        Ok((self.required + used).saturating_sub(self.disk_size))
+    }
+
+    /// Finds the smallest single directory that frees enough space.
+    /// Returns `Ok(None)` if nothing has to be deleted.
+    pub fn best_single(&self, fs: &FileSystem) -> Result<Option<Deletion>, PlanError> {
+        let to_free = self.to_free(fs)?;
+        if to_free == 0 {
+            return Ok(None);
+        }
+        // the root frees everything, so there is always a directory big enough
+        let (dir, freed) = fs
+            .dir_sizes()
+            .into_iter()
+            .filter(|&(_, size)| size >= to_free)
+            .min_by_key(|&(id, size)| (size, id))
+            .expect("The root is always big enough");
+
+        Ok(Some(Deletion {
+            dirs: vec![dir],
+            freed,
+        }))
+    }
 
-        // if the cd commands come up, change the current_pos
-        if line.starts_with("$ cd") {
-            let dir = &line["& cd ".len()..];
-            match dir {
-                ".." => {
-                    current_pos.pop();
+    /// Finds the set of directories, none inside another, that frees enough space
+    /// while deleting as little as possible.
+    /// Returns `Ok(None)` if nothing has to be deleted.
+    ///
+    /// This is a knapsack over the directories in depth first order: at every directory
+    /// we either delete it and skip everything inside, or go on with its content.
+    /// The reachable sizes below the space to free are kept as a sparse bitset per directory,
+    /// so the memory grows with the sizes that can actually be freed and not with the disk size.
+    pub fn best_set(&self, fs: &FileSystem) -> Result<Option<Deletion>, PlanError> {
+        let to_free = self.to_free(fs)?;
+        if to_free == 0 {
+            return Ok(None);
+        }
+
+        // the directories in depth first order with their size and the index behind their content
+        let mut sizes = vec![0; fs.len()];
+        for (id, size) in fs.dir_sizes() {
+            sizes[id.0] = size;
+        }
+        let depths = fs.depths();
+        let dirs: Vec<NodeId> = fs.walk().filter(|&id| fs.node(id).is_dir()).collect();
+        let mut ends = vec![dirs.len(); dirs.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (index, &dir) in dirs.iter().enumerate() {
+            // every open directory that isn't a parent of this one is finished
+            while let Some(&last) = open.last() {
+                if depths[dirs[last].0] < depths[dir.0] {
+                    break;
                 }
-                "/" => {
-                    current_pos = vec!["/"];
+                ends[last] = index;
+                open.pop();
+            }
+            open.push(index);
+        }
+
+        // reachable[i] holds the sizes below `to_free`, that can be freed before dirs[i]
+        let mut reachable = vec![SparseBitset::new(to_free); dirs.len() + 1];
+        reachable[0].insert(0);
+        // the best deletion as (freed, index of the last deleted dir, freed before it)
+        let mut best: Option<(u64, usize, u64)> = None;
+
+        for index in 0..dirs.len() {
+            let size = sizes[dirs[index].0];
+            let current = reachable[index].clone();
+
+            // deleting this directory is enough, if a size of at least `to_free - size` was reached
+            if let Some(before) = current.first_from(to_free.saturating_sub(size)) {
+                let freed = before + size;
+                if best.is_none_or(|(best_freed, _, _)| freed < best_freed) {
+                    best = Some((freed, index, before));
+                }
+            }
+
+            reachable[ends[index]].union_shifted(&current, size);
+            reachable[index + 1].union_shifted(&current, 0);
+        }
+
+        // walk back to collect the deleted directories
+        let (freed, mut index, mut before) = best.expect("The root is always big enough");
+        let mut deleted = vec![dirs[index]];
+        while before > 0 {
+            if let Some(previous) = (0..index).find(|&previous| {
+                let size = sizes[dirs[previous].0];
+                ends[previous] == index
+                    && size <= before
+                    && reachable[previous].contains(before - size)
+            }) {
+                before -= sizes[dirs[previous].0];
+                deleted.push(dirs[previous]);
+                index = previous;
+            } else {
+                index -= 1;
+            }
+        }
+        deleted.reverse();
+
+        Ok(Some(Deletion {
+            dirs: deleted,
+            freed,
+        }))
+    }
+}
+
+/// A set of the numbers below a limit, stored as the non empty 64 bit words of a bitset,
+/// so it only takes memory for the numbers it contains
+#[derive(Debug, Clone)]
+struct SparseBitset {
+    len: u64,
+    words: BTreeMap<u64, u64>,
+}
+
+impl SparseBitset {
+    /// a set for the numbers below `len`
+    fn new(len: u64) -> Self {
+        Self {
+            len,
+            words: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, value: u64) {
+        self.or_word(value / 64, 1 << (value % 64));
+    }
+
+    fn contains(&self, value: u64) -> bool {
+        value < self.len
+            && self
+                .words
+                .get(&(value / 64))
+                .is_some_and(|word| word & (1 << (value % 64)) != 0)
+    }
+
+    /// The smallest value in the set that is at least `from`
+    fn first_from(&self, from: u64) -> Option<u64> {
+        for (&index, &word) in self.words.range(from / 64..) {
+            let word = if index == from / 64 {
+                word & (u64::MAX << (from % 64))
+            } else {
+                word
+            };
+            if word != 0 {
+                let value = index * 64 + word.trailing_zeros() as u64;
+                return (value < self.len).then_some(value);
+            }
+        }
+        None
+    }
+
+    /// Adds every value of `other` increased by `shift`, dropping everything too big
+    fn union_shifted(&mut self, other: &SparseBitset, shift: u64) {
+        let word_shift = shift / 64;
+        let bit_shift = shift % 64;
+        let word_count = self.len.div_ceil(64);
+        for (&index, &word) in &other.words {
+            let target = index + word_shift;
+            if target >= word_count {
+                break;
+            }
+            self.or_word(target, word << bit_shift);
+            if bit_shift > 0 && target + 1 < word_count {
+                self.or_word(target + 1, word >> (64 - bit_shift));
+            }
+        }
+        // clear the values beyond the end, so `first_from` never finds them
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = self.words.get_mut(&(self.len / 64)) {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+    }
+
+    fn or_word(&mut self, index: u64, word: u64) {
+        if word != 0 {
+            *self.words.entry(index).or_default() |= word;
+        }
+    }
+}
+
+/// Renders the file system like the puzzle description, keeping the listed order
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_07::{handle_file_system, render_listing};
+///
+/// let fs = handle_file_system("$ cd /\n$ ls\ndir a\n12 b").unwrap();
+/// assert_eq!(render_listing(&fs, None), "- / (dir)\n  - a (dir)\n  - b (file, size=12)\n");
+/// ```
+pub fn render_listing(fs: &FileSystem, max_depth: Option<usize>) -> String {
+    let mut out = String::new();
+    // an explicit stack, deep trees would overflow the call stack
+    let mut stack = vec![(fs.root(), 0)];
+    while let Some((id, depth)) = stack.pop() {
+        let indent = "  ".repeat(depth);
+        match fs.node(id) {
+            Node::File { name, size, .. } => {
+                out.push_str(&format!("{indent}- {name} (file, size={size})\n"));
+            }
+            Node::Dir { name, children, .. } => {
+                out.push_str(&format!("{indent}- {name} (dir)\n"));
+                if max_depth.is_some_and(|max| depth >= max) {
+                    continue;
                 }
-                other => current_pos.push(other),
+                // push in reverse, so the children come out in the listed order
+                stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
             }
+        }
+    }
+    out
+}
+
+/// Renders the file system like the Unix `tree` command, sorted by name
+pub fn render_tree(fs: &FileSystem, max_depth: Option<usize>) -> String {
+    let mut out = String::from("/\n");
+    let (mut dirs, mut files) = (0, 0);
+    // the children left to render of every open directory together with the prefix
+    // of their lines, an explicit stack as deep trees would overflow the call stack
+    let mut stack: Vec<(Vec<NodeId>, String)> = Vec::new();
+    if max_depth.is_none_or(|max| max >= 1) {
+        stack.push((sorted_children(fs, fs.root()), String::new()));
+    }
+
+    while let Some((children, prefix)) = stack.last_mut() {
+        let Some(child) = children.pop() else {
+            stack.pop();
             continue;
+        };
+        let last = children.is_empty();
+        let node = fs.node(child);
+        out.push_str(&format!(
+            "{prefix}{}{}\n",
+            if last { "└── " } else { "├── " },
+            node.name()
+        ));
+        if node.is_dir() {
+            dirs += 1;
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            // the children of the child are one level below the open directories
+            if max_depth.is_none_or(|max| stack.len() < max) {
+                stack.push((sorted_children(fs, child), prefix));
+            }
+        } else {
+            files += 1;
+        }
+    }
+
+    out.push_str(&format!(
+        "\n{dirs} director{}, {files} file{}\n",
+        if dirs == 1 { "y" } else { "ies" },
+        if files == 1 { "" } else { "s" }
+    ));
+    out
+}
+
+/// The children of a directory sorted by name in reverse, so they can be popped in order
+fn sorted_children(fs: &FileSystem, dir: NodeId) -> Vec<NodeId> {
+    let mut children = fs.node(dir).children().to_vec();
+    children.sort_by(|a, b| fs.node(*b).name().cmp(fs.node(*a).name()));
+    children
+}
+
+/// Renders the total size of the directories like `du -h`, the biggest first.
+/// Directories deeper than `max_depth` aren't shown, but still count for their parents.
+pub fn render_du(fs: &FileSystem, max_depth: Option<usize>) -> String {
+    let depths = fs.depths();
+    let mut sizes: Vec<(u64, String)> = fs
+        .dir_sizes()
+        .into_iter()
+        .filter(|&(id, _)| max_depth.is_none_or(|max| depths[id.0] <= max))
+        .map(|(id, size)| (size, fs.path(id)))
+        .collect();
+    // biggest first, equal sizes by path
+    sizes.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    sizes
+        .iter()
+        .map(|(size, path)| format!("{}\t{path}\n", human_size(*size)))
+        .collect()
+}
+
+/// Formats a size like `du -h`, e.g. `584`, `2.5K` or `47M`
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value = size as f64;
+    let mut unit = "";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    // like du, small values get one decimal and everything is rounded up
+    if value < 10.0 {
+        let value = (value * 10.0).ceil() / 10.0;
+        if value < 10.0 {
+            return format!("{value:.1}{unit}");
+        }
+    }
+    format!("{}{unit}", value.ceil())
+}
+
+impl fmt::Display for FileSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", render_listing(self, None))
+    }
+}
+
+/// Errors that make it impossible to write a transcript for a file system.
+/// The `path` is the absolute path of the offending node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    /// a name that can't show up in a terminal output, like `..` or one with a space
+    InvalidName { path: String },
+    /// two nodes with the same name in one directory
+    DuplicateName { path: String },
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GenerateError::InvalidName { path } => write!(f, "'{path}' has an invalid name"),
+            GenerateError::DuplicateName { path } => write!(f, "'{path}' exists twice"),
         }
+    }
+}
 
-        // handle the file size stuff
+impl std::error::Error for GenerateError {}
 
-        let file_parts: Vec<&str> = line.split(" ").collect();
-        // get the size - the file name isn't important
-        let filesize = *file_parts.get(0).expect("The input is wrong");
+/// Writes a terminal output that explores the whole file system with `cd` and `ls`.
+/// Parsing the output with `handle_file_system` gives the same file system again.
+///
+/// Fails for names that wouldn't parse back the same: empty ones, `..`, and names with
+/// a `/` or whitespace, as well as two nodes with the same name in one directory.
+pub fn generate_transcript(fs: &FileSystem) -> Result<String, GenerateError> {
+    check_names(fs)?;
 
-        let filesize: u32 = filesize.parse().unwrap();
+    let mut out = String::from("$ cd /\n");
+    // an explicit stack, deep trees would overflow the call stack.
+    // `None` is the way back up to the parent.
+    let mut stack = vec![Some(fs.root())];
+    while let Some(entry) = stack.pop() {
+        let Some(dir) = entry else {
+            out.push_str("$ cd ..\n");
+            continue;
+        };
+        if dir != fs.root() {
+            out.push_str(&format!("$ cd {}\n", fs.node(dir).name()));
+        }
+        out.push_str("$ ls\n");
+        let children = fs.node(dir).children();
+        for &child in children {
+            match fs.node(child) {
+                Node::Dir { name, .. } => out.push_str(&format!("dir {name}\n")),
+                Node::File { name, size, .. } => out.push_str(&format!("{size} {name}\n")),
+            }
+        }
+        // push in reverse, so the directories are visited in the listed order
+        for &child in children.iter().rev() {
+            if fs.node(child).is_dir() {
+                stack.push(None);
+                stack.push(Some(child));
+            }
+        }
+    }
+    Ok(out)
+}
 
-        let mut path = String::new();
-        // add to every folder the filesize
-        for part in &current_pos {
-            // handle the path
-            if !path.ends_with('/') && !path.is_empty() {
-                path.push('/');
+/// Checks that every name can be written to a transcript, see `generate_transcript`
+fn check_names(fs: &FileSystem) -> Result<(), GenerateError> {
+    for dir in fs.walk().filter(|&id| fs.node(id).is_dir()) {
+        let mut names = HashSet::new();
+        for &child in fs.node(dir).children() {
+            let name = fs.node(child).name();
+            if name.is_empty()
+                || name == ".."
+                || name.contains(|c: char| c == '/' || c.is_whitespace())
+            {
+                return Err(GenerateError::InvalidName {
+                    path: fs.path(child),
+                });
             }
-            path.push_str(part);
+            if !names.insert(name) {
+                return Err(GenerateError::DuplicateName {
+                    path: fs.path(child),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parameters for `random_file_system`, every directory gets a random number of
+/// sub directories and files within the limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomTree {
+    pub seed: u64,
+    /// the deepest level with directories, the root has a depth of 0
+    pub max_depth: usize,
+    /// the fewest sub directories of a directory above `max_depth`
+    pub min_fan_out: usize,
+    pub max_fan_out: usize,
+    pub max_files: usize,
+    pub max_file_size: u64,
+}
+
+impl Default for RandomTree {
+    fn default() -> Self {
+        Self {
+            seed: 2022,
+            max_depth: 5,
+            min_fan_out: 0,
+            max_fan_out: 3,
+            max_files: 5,
+            max_file_size: 300_000,
+        }
+    }
+}
+
+/// Builds a random file system, the same parameters always build the same one
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_07::{
+///     generate_transcript, handle_file_system, random_file_system, RandomTree,
+/// };
+///
+/// let fs = random_file_system(RandomTree { seed: 7, ..Default::default() });
+/// let transcript = generate_transcript(&fs).unwrap();
+/// assert_eq!(handle_file_system(&transcript).unwrap(), fs);
+/// ```
+pub fn random_file_system(params: RandomTree) -> FileSystem {
+    let mut rng = Rng::new(params.seed);
+    let mut fs = FileSystem::new();
+    let mut level = vec![fs.root()];
+    for depth in 0..=params.max_depth {
+        let mut next_level = Vec::new();
+        for dir in level {
+            let dirs = if depth < params.max_depth {
+                let spread = params.max_fan_out.saturating_sub(params.min_fan_out);
+                params.min_fan_out as u64 + rng.below(spread as u64 + 1)
+            } else {
+                0
+            };
+            let files = rng.below(params.max_files as u64 + 1);
+            let mut names: HashSet<String> = HashSet::new();
+            let mut kinds: Vec<bool> = (0..dirs).map(|_| true).collect();
+            kinds.extend((0..files).map(|_| false));
+            rng.shuffle(&mut kinds);
 
-            match file_system.get_mut(&path) {
-                Some(dir) => *dir += filesize, // add the size to the path
-                None => { // if the folder path not exists add the path and the size
-                    file_system.insert(path.clone(), filesize);
+            for is_dir in kinds {
+                let name = loop {
+                    let name = random_name(&mut rng, is_dir);
+                    if names.insert(name.clone()) {
+                        break name;
+                    }
+                };
+                if is_dir {
+                    next_level.extend(fs.add_dir(dir, &name));
+                } else {
+                    let size = rng.below(params.max_file_size) + 1;
+                    fs.add_file(dir, &name, size);
                 }
             }
         }
+        level = next_level;
     }
-    file_system
+    fs
 }
 
+/// a random name, files sometimes get an extension
+fn random_name(rng: &mut Rng, is_dir: bool) -> String {
+    let len = rng.below(8) + 1;
+    let mut name: String = (0..len)
+        .map(|_| (b'a' + rng.below(26) as u8) as char)
+        .collect();
+    if !is_dir && rng.below(2) == 0 {
+        let extension = ["txt", "dat", "log", "lst", "ext"][rng.below(5) as usize];
+        name.push('.');
+        name.push_str(extension);
+    }
+    name
+}
+
+pub const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
 #[cfg(test)]
 mod tests_day_07 {
 
@@ -125,29 +1070,462 @@ mod tests_day_07 {
             sum
         );
     }
-}
 
-pub const EXAMPLE: &str = "\
-$ cd /
-$ ls
-dir a
-14848514 b.txt
-8504156 c.dat
-dir d
-$ cd a
-$ ls
-dir e
-29116 f
-2557 g
-62596 h.lst
-$ cd e
-$ ls
-584 i
-$ cd ..
-$ cd ..
-$ cd d
-$ ls
-4060174 j
-8033020 d.log
-5626152 d.ext
-7214296 k";
+    #[test]
+    fn example_tree() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        assert_eq!(fs.len(), 14);
+        assert_eq!(fs.du(fs.root()), 48381165);
+        assert_eq!(fs.du(fs.find("/a").unwrap()), 94853);
+        assert_eq!(fs.du(fs.find("/a/e").unwrap()), 584);
+        assert_eq!(fs.du(fs.find("/d").unwrap()), 24933642);
+
+        let file = fs.find("/a/h.lst").unwrap();
+        assert_eq!(
+            fs.node(file),
+            &Node::File {
+                name: String::from("h.lst"),
+                size: 62596,
+                parent: fs.find("/a/").unwrap(),
+            }
+        );
+        assert_eq!(fs.path(file), "/a/h.lst");
+        assert_eq!(fs.find("/a/x"), None);
+        assert_eq!(fs.find("/b.txt/x"), None);
+    }
+
+    #[test]
+    fn example_walk() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let paths: Vec<String> = fs.walk().map(|id| fs.path(id)).collect();
+        assert_eq!(
+            paths[..6],
+            ["/", "/a", "/a/e", "/a/e/i", "/a/f", "/a/g"].map(String::from)
+        );
+        assert_eq!(paths.len(), fs.len());
+    }
+
+    #[test]
+    fn dir_sizes_match_du() {
+        let fs = handle_file_system(&load_input(7)).unwrap();
+        for (id, size) in fs.dir_sizes() {
+            assert_eq!(size, fs.du(id));
+        }
+    }
+
+    #[test]
+    fn inconsistent_transcripts() {
+        let result = handle_file_system("$ cd /\n$ ls\n12 a\n12 a");
+        assert_eq!(
+            result,
+            Err(TranscriptError::DuplicateEntry {
+                line: 4,
+                name: String::from("a")
+            })
+        );
+
+        let result = handle_file_system("$ cd /\n$ ls\ndir a\n$ cd b");
+        assert_eq!(
+            result,
+            Err(TranscriptError::UnknownDirectory {
+                line: 4,
+                name: String::from("b")
+            })
+        );
+
+        let result = handle_file_system("$ ls\n12 a\n$ ls\n13 a");
+        assert_eq!(
+            result,
+            Err(TranscriptError::ConflictingEntry {
+                line: 4,
+                name: String::from("a")
+            })
+        );
+
+        let result = handle_file_system("$ ls\n12 a\n$ cd a");
+        assert!(matches!(
+            result,
+            Err(TranscriptError::NotADirectory { line: 3, .. })
+        ));
+
+        let result = handle_file_system("$ cd ..");
+        assert_eq!(result, Err(TranscriptError::AboveRoot { line: 1 }));
+
+        let result = handle_file_system("$ cd /\n12 a");
+        assert_eq!(result, Err(TranscriptError::UnexpectedOutput { line: 2 }));
+
+        let result = handle_file_system("$ ls\nabc a");
+        assert_eq!(result, Err(TranscriptError::InvalidLine { line: 2 }));
+    }
+
+    #[test]
+    fn files_have_no_children() {
+        let mut fs = FileSystem::new();
+        let file = fs.add_file(fs.root(), "a", 12).unwrap();
+        assert_eq!(fs.add_dir(file, "b"), None);
+        assert_eq!(fs.add_file(file, "c", 1), None);
+        assert_eq!(fs.len(), 2);
+    }
+
+    #[test]
+    fn relisting_is_consistent() {
+        let fs = handle_file_system("$ ls\ndir a\n12 b\n$ ls\n12 b\ndir a").unwrap();
+        assert_eq!(fs.len(), 3);
+    }
+
+    #[test]
+    fn example_listing() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let expected = "\
+- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+";
+        assert_eq!(fs.to_string(), expected);
+
+        let expected = "\
+- / (dir)
+  - a (dir)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+";
+        assert_eq!(render_listing(&fs, Some(1)), expected);
+    }
+
+    #[test]
+    fn example_tree_rendering() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let expected = "\
+/
+├── a
+│   ├── e
+│   │   └── i
+│   ├── f
+│   ├── g
+│   └── h.lst
+├── b.txt
+├── c.dat
+└── d
+    ├── d.ext
+    ├── d.log
+    ├── j
+    └── k
+
+3 directories, 10 files
+";
+        assert_eq!(render_tree(&fs, None), expected);
+
+        let expected = "\
+/
+├── a
+│   ├── e
+│   ├── f
+│   ├── g
+│   └── h.lst
+├── b.txt
+├── c.dat
+└── d
+    ├── d.ext
+    ├── d.log
+    ├── j
+    └── k
+
+3 directories, 9 files
+";
+        assert_eq!(render_tree(&fs, Some(2)), expected);
+    }
+
+    #[test]
+    fn example_du() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        assert_eq!(
+            render_du(&fs, None),
+            "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n"
+        );
+        assert_eq!(render_du(&fs, Some(0)), "47M\t/\n");
+    }
+
+    #[test]
+    fn human_sizes() {
+        assert_eq!(human_size(0), "0");
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+        assert_eq!(human_size(10 * 1024), "10K");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0G");
+    }
+
+    #[test]
+    fn example_best_single() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let plan = DeletionPlanner::default().best_single(&fs).unwrap();
+        assert_eq!(
+            plan,
+            Some(Deletion {
+                dirs: vec![fs.find("/d").unwrap()],
+                freed: 24933642
+            })
+        );
+
+        // `d` frees exactly what is needed
+        let used = fs.du(fs.root());
+        let planner = DeletionPlanner::new(used, 24933642);
+        let plan = planner.best_single(&fs).unwrap().unwrap();
+        assert_eq!(plan.dirs, vec![fs.find("/d").unwrap()]);
+    }
+
+    #[test]
+    fn nothing_to_delete() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let planner = DeletionPlanner::new(100_000_000, 30_000_000);
+        assert_eq!(planner.to_free(&fs), Ok(0));
+        assert_eq!(planner.best_single(&fs), Ok(None));
+        assert_eq!(planner.best_set(&fs), Ok(None));
+    }
+
+    #[test]
+    fn impossible_plans() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        assert_eq!(
+            DeletionPlanner::new(1000, 10).best_single(&fs),
+            Err(PlanError::UsedExceedsDisk {
+                used: 48381165,
+                disk_size: 1000
+            })
+        );
+        assert_eq!(
+            DeletionPlanner::new(70_000_000, 80_000_000).best_set(&fs),
+            Err(PlanError::RequiredExceedsDisk {
+                required: 80_000_000,
+                disk_size: 70_000_000
+            })
+        );
+    }
+
+    #[test]
+    fn best_set_combines_directories() {
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        for (name, size) in [("a", 40), ("b", 35), ("c", 70)] {
+            let dir = fs.add_dir(root, name).unwrap();
+            fs.add_file(dir, "file", size).unwrap();
+        }
+        let planner = DeletionPlanner::new(145, 70);
+
+        // a single directory has to be `c`, but `a` and `b` together are smaller
+        let single = planner.best_single(&fs).unwrap().unwrap();
+        assert_eq!(single.freed, 70);
+        let set = planner.best_set(&fs).unwrap().unwrap();
+        assert_eq!(set.freed, 70);
+        assert_eq!(set.dirs, vec![fs.find("/c").unwrap()]);
+
+        let planner = DeletionPlanner::new(145, 75);
+        let single = planner.best_single(&fs).unwrap().unwrap();
+        assert_eq!(single.freed, 145);
+        let set = planner.best_set(&fs).unwrap().unwrap();
+        assert_eq!(set.freed, 75);
+        assert_eq!(
+            set.dirs,
+            vec![fs.find("/a").unwrap(), fs.find("/b").unwrap()]
+        );
+    }
+
+    #[test]
+    fn best_set_on_a_huge_disk() {
+        // a few terabytes of data, far too much for a dense bitset
+        let mut fs = FileSystem::new();
+        let root = fs.root();
+        for (name, size) in [
+            ("a", 3_000_000_000_000),
+            ("b", 2_000_000_000_001),
+            ("c", 4_000_000_000_000),
+        ] {
+            let dir = fs.add_dir(root, name).unwrap();
+            fs.add_file(dir, "file", size).unwrap();
+        }
+        let planner = DeletionPlanner::new(10_000_000_000_000, 6_000_000_000_000);
+        let set = planner.best_set(&fs).unwrap().unwrap();
+        assert_eq!(set.freed, 5_000_000_000_001);
+        assert_eq!(
+            set.dirs,
+            vec![fs.find("/a").unwrap(), fs.find("/b").unwrap()]
+        );
+    }
+
+    #[test]
+    fn best_set_input() {
+        let fs = handle_file_system(&load_input(7)).unwrap();
+        let planner = DeletionPlanner::default();
+        let single = planner.best_single(&fs).unwrap().unwrap();
+        let set = planner.best_set(&fs).unwrap().unwrap();
+        assert!(set.freed <= single.freed);
+        assert!(set.freed >= planner.to_free(&fs).unwrap());
+
+        // the chosen directories aren't nested
+        for &a in &set.dirs {
+            for &b in &set.dirs {
+                assert!(a == b || !fs.path(b).starts_with(&format!("{}/", fs.path(a))));
+            }
+        }
+    }
+
+    #[test]
+    fn best_set_matches_brute_force() {
+        let fs = handle_file_system(&load_input(7)).unwrap();
+        // only look at the small directories in `/` to keep the brute force small
+        let mut small = FileSystem::new();
+        let root = small.root();
+        let dirs: Vec<(NodeId, u64)> = fs.dir_sizes().into_iter().skip(1).take(12).collect();
+        for &(id, size) in &dirs {
+            let dir = small.add_dir(root, fs.node(id).name()).unwrap();
+            small.add_file(dir, "file", size).unwrap();
+        }
+        let used = small.du(root);
+
+        for required in [used / 7, used / 3, used / 2, used - 1] {
+            let planner = DeletionPlanner::new(used, required);
+            let to_free = planner.to_free(&small).unwrap();
+            let brute_force = (1..1u32 << dirs.len())
+                .map(|mask| {
+                    dirs.iter()
+                        .enumerate()
+                        .filter(|(index, _)| mask & (1 << index) != 0)
+                        .map(|(_, &(_, size))| size)
+                        .sum::<u64>()
+                })
+                .filter(|&freed| freed >= to_free)
+                .min()
+                .unwrap();
+            let set = planner.best_set(&small).unwrap().unwrap();
+            assert_eq!(set.freed, brute_force);
+            let freed: u64 = set.dirs.iter().map(|&dir| small.du(dir)).sum();
+            assert_eq!(freed, set.freed);
+        }
+    }
+
+    #[test]
+    fn example_transcript_round_trip() {
+        let fs = handle_file_system(EXAMPLE).unwrap();
+        let transcript = generate_transcript(&fs).unwrap();
+        assert!(transcript.starts_with("$ cd /\n$ ls\ndir a\n14848514 b.txt\n"));
+        assert_eq!(handle_file_system(&transcript).unwrap(), fs);
+    }
+
+    #[test]
+    fn hand_built_transcript_round_trip() {
+        // the parser adds `y` before `a/x`, but the tree is the same
+        let mut fs = FileSystem::new();
+        let a = fs.add_dir(fs.root(), "a").unwrap();
+        fs.add_file(a, "x", 10).unwrap();
+        fs.add_file(fs.root(), "y", 20).unwrap();
+        let parsed = handle_file_system(&generate_transcript(&fs).unwrap()).unwrap();
+        assert_eq!(parsed, fs);
+        assert_eq!(parsed.find("/a/x"), Some(NodeId(3)));
+        assert_eq!(fs.find("/a/x"), Some(NodeId(2)));
+
+        // another size, name or order of the children is another tree
+        let mut other = FileSystem::new();
+        let a = other.add_dir(other.root(), "a").unwrap();
+        other.add_file(a, "x", 11).unwrap();
+        other.add_file(other.root(), "y", 20).unwrap();
+        assert_ne!(other, fs);
+        let mut other = FileSystem::new();
+        other.add_file(other.root(), "y", 20).unwrap();
+        let a = other.add_dir(other.root(), "a").unwrap();
+        other.add_file(a, "x", 10).unwrap();
+        assert_ne!(other, fs);
+        let mut other = FileSystem::new();
+        let a = other.add_dir(other.root(), "b").unwrap();
+        other.add_file(a, "x", 10).unwrap();
+        other.add_file(other.root(), "y", 20).unwrap();
+        assert_ne!(other, fs);
+        let mut other = FileSystem::new();
+        other.add_file(other.root(), "a", 10).unwrap();
+        other.add_file(other.root(), "y", 20).unwrap();
+        assert_ne!(other, fs);
+    }
+
+    #[test]
+    fn random_transcript_round_trip() {
+        for seed in 0..20 {
+            let params = RandomTree {
+                seed,
+                max_depth: 8,
+                ..Default::default()
+            };
+            let fs = random_file_system(params);
+            let transcript = generate_transcript(&fs).unwrap();
+            assert_eq!(handle_file_system(&transcript).unwrap(), fs);
+            assert_eq!(
+                generate_transcript(&random_file_system(params)).unwrap(),
+                transcript
+            );
+        }
+    }
+
+    #[test]
+    fn random_deep_tree() {
+        // a single chain of directories, far too deep for recursion
+        let fs = random_file_system(RandomTree {
+            seed: 1,
+            max_depth: 100_000,
+            min_fan_out: 1,
+            max_fan_out: 1,
+            max_files: 1,
+            ..Default::default()
+        });
+        let deepest = fs.walk().filter(|&id| fs.node(id).is_dir()).last().unwrap();
+        assert_eq!(fs.depth(deepest), 100_000);
+        assert_eq!(fs.dir_sizes().len(), 100_001);
+        assert_eq!(fs.du(fs.root()), fs.dir_sizes()[0].1);
+
+        let transcript = generate_transcript(&fs).unwrap();
+        assert_eq!(handle_file_system(&transcript).unwrap(), fs);
+        // the directories down to depth 3 and maybe a file in all but the root
+        let lines = render_listing(&fs, Some(3)).lines().count();
+        assert!((4..=7).contains(&lines));
+
+        // the planner has to work on generated trees too
+        let used = fs.du(fs.root());
+        let planner = DeletionPlanner::new(used + 10, used / 2);
+        let set = planner.best_set(&fs).unwrap().unwrap();
+        assert!(set.freed >= planner.to_free(&fs).unwrap());
+    }
+
+    #[test]
+    fn invalid_transcript_names() {
+        for name in ["", "..", "a b", "a\nb", "a/b"] {
+            let mut fs = FileSystem::new();
+            let dir = fs.add_dir(fs.root(), "d").unwrap();
+            fs.add_file(dir, name, 12).unwrap();
+            assert_eq!(
+                generate_transcript(&fs),
+                Err(GenerateError::InvalidName {
+                    path: format!("/d/{name}")
+                })
+            );
+        }
+
+        let mut fs = FileSystem::new();
+        fs.add_dir(fs.root(), "a").unwrap();
+        fs.add_file(fs.root(), "a", 12).unwrap();
+        assert_eq!(
+            generate_transcript(&fs),
+            Err(GenerateError::DuplicateName {
+                path: String::from("/a")
+            })
+        );
+    }
+}
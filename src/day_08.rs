@@ -1,136 +1,462 @@
 #![doc = include_str!("descriptions/day_08.md")]
 
+use std::fmt::Write;
+use std::str::FromStr;
+
 pub fn solve_star_one(input: &str) -> u32 {
-    let trees = parse_trees(input);
-    let height = trees.len();
-    let width = trees[0].len();
-    let mut count: u32 = (2 * height + 2 * width - 4) as u32;
-
-    for y in 1..(height - 1) {
-        for x in 1..(width - 1) {
-            let tree_height = trees[y][x];
-
-            // we assume the tree is visible from all sides and correct that in this interation
-            let mut visible_from_left = true;
-            let mut visible_from_right = true;
-            let mut visible_from_top = true;
-            let mut visible_from_bottom = true;
-
-            for y2 in 0..y {
-                if trees[y2][x] >= tree_height {
-                    visible_from_top = false;
-                    break;
-                }
-            }
+    let forest = Forest::from_str(input).expect("The input isn't a valid forest");
+    forest.count_visible(&ViewRules::default())
+}
 
-            for y2 in y + 1..height {
-                if trees[y2][x] >= tree_height {
-                    visible_from_bottom = false;
-                    break;
-                }
-            }
+pub fn solve_star_two(input: &str) -> u64 {
+    let forest = Forest::from_str(input).expect("The input isn't a valid forest");
+    forest.max_scenic_score(&ViewRules::default())
+}
+
+/// Returns for every tree the sides of the forest it is visible from
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_08::{visibility_map, Sides, EXAMPLE};
+///
+/// let map = visibility_map(EXAMPLE);
+/// assert_eq!(map[1][1], Sides::LEFT | Sides::TOP);
+/// assert!(!map[1][3].is_visible());
+/// ```
+pub fn visibility_map(input: &str) -> Vec<Vec<Sides>> {
+    let forest = Forest::from_str(input).expect("The input isn't a valid forest");
+    forest.visibility(&ViewRules::default())
+}
+
+/// Returns the scenic score of every tree
+pub fn scenic_score_map(input: &str) -> Vec<Vec<u64>> {
+    let forest = Forest::from_str(input).expect("The input isn't a valid forest");
+    forest.scenic_scores(&ViewRules::default())
+}
+
+/// Finds the tree with the highest scenic score as `(x, y, score)`.
+/// The first tree in reading order wins if there are more than one.
+pub fn best_spot(input: &str) -> Option<(usize, usize, u64)> {
+    scenic_score_map(input)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.into_iter()
+                .enumerate()
+                .map(move |(x, score)| (x, y, score))
+        })
+        .reduce(|best, spot| if spot.2 > best.2 { spot } else { best })
+}
+
+/// How the heights are written in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForestFormat {
+    /// one digit per tree like in the puzzle, e.g. `30373`
+    Digits,
+    /// heights separated by a character, e.g. `3,10,37`
+    Separated(char),
+    /// heights separated by whitespace, e.g. `3 10 37`
+    Whitespace,
+}
+
+/// Which trees block the view of a tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOfSight {
+    /// only strictly shorter trees can be seen over, like in the puzzle
+    StrictlyShorter,
+    /// trees of the same height can be seen over too
+    ShorterOrEqual,
+}
+
+impl LineOfSight {
+    fn blocks(&self, other: u32, tree: u32) -> bool {
+        match self {
+            LineOfSight::StrictlyShorter => other >= tree,
+            LineOfSight::ShorterOrEqual => other > tree,
+        }
+    }
+}
+
+/// The directions to look into and what blocks the view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewRules {
+    /// steps as `(x, y)` looking from the tree towards the edge, with `y` going down.
+    /// The n-th direction is `Sides::direction(n)`.
+    directions: Vec<(i32, i32)>,
+    pub line_of_sight: LineOfSight,
+}
+
+impl Default for ViewRules {
+    /// the four axes, in the order of `Sides::LEFT`, `RIGHT`, `TOP` and `BOTTOM`
+    fn default() -> Self {
+        Self {
+            directions: vec![(-1, 0), (1, 0), (0, -1), (0, 1)],
+            line_of_sight: LineOfSight::StrictlyShorter,
+        }
+    }
+}
+
+impl ViewRules {
+    /// Rules for any directions, `None` for a `(0, 0)` step or more than
+    /// `Sides::MAX_DIRECTIONS` directions
+    pub fn new(directions: Vec<(i32, i32)>, line_of_sight: LineOfSight) -> Option<Self> {
+        if directions.len() > Sides::MAX_DIRECTIONS || directions.contains(&(0, 0)) {
+            return None;
+        }
+        Some(Self {
+            directions,
+            line_of_sight,
+        })
+    }
+
+    pub fn directions(&self) -> &[(i32, i32)] {
+        &self.directions
+    }
+
+    /// the four axes followed by the four diagonals
+    pub fn with_diagonals() -> Self {
+        let mut rules = Self::default();
+        rules
+            .directions
+            .extend([(-1, -1), (1, -1), (-1, 1), (1, 1)]);
+        rules
+    }
+}
+
+/// Why the input isn't a forest, with the 1-based line and column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForestError {
+    /// something that isn't a whole number from 0 up
+    InvalidHeight {
+        height: String,
+        line: usize,
+        column: usize,
+    },
+    /// a row with another number of trees than the first row
+    RaggedRow {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+}
+
+impl std::fmt::Display for ForestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForestError::InvalidHeight {
+                height,
+                line,
+                column,
+            } => write!(
+                f,
+                "line {line}, column {column}: '{height}' isn't a height from 0 up"
+            ),
+            ForestError::RaggedRow {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "line {line}: the row has {width} trees instead of {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ForestError {}
+
+/// A grid of tree heights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forest {
+    trees: Vec<Vec<u32>>,
+}
+
+impl FromStr for Forest {
+    type Err = ForestError;
+
+    /// parses the puzzle format with one digit per tree
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Forest::parse(s, ForestFormat::Digits)
+    }
+}
 
-            for x2 in 0..x {
-                if trees[y][x2] >= tree_height {
-                    visible_from_left = false;
-                    break;
+impl Forest {
+    pub fn parse(input: &str, format: ForestFormat) -> Result<Self, ForestError> {
+        let mut trees: Vec<Vec<u32>> = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let row = parse_row(line, index + 1, format)?;
+            if let Some(first) = trees.first() {
+                if row.len() != first.len() {
+                    return Err(ForestError::RaggedRow {
+                        line: index + 1,
+                        width: row.len(),
+                        expected: first.len(),
+                    });
                 }
             }
+            trees.push(row);
+        }
+        Ok(Self { trees })
+    }
+
+    pub fn height(&self) -> usize {
+        self.trees.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.trees.first().map_or(0, |row| row.len())
+    }
+
+    /// the height of the tree at `(x, y)`
+    pub fn get(&self, x: usize, y: usize) -> Option<u32> {
+        self.trees.get(y)?.get(x).copied()
+    }
+
+    /// Returns for every tree the directions it is visible from
+    pub fn visibility(&self, rules: &ViewRules) -> Vec<Vec<Sides>> {
+        visible_grid(&self.trees, rules)
+    }
+
+    /// the scenic score of every tree, see `scenic_scores` for when it saturates
+    pub fn scenic_scores(&self, rules: &ViewRules) -> Vec<Vec<u64>> {
+        scenic_scores(&self.trees, rules)
+    }
 
-            for x2 in x + 1..width {
-                if trees[y][x2] >= tree_height {
-                    visible_from_right = false;
-                    break;
+    /// the number of trees visible from at least one direction
+    pub fn count_visible(&self, rules: &ViewRules) -> u32 {
+        self.visibility(rules)
+            .iter()
+            .flatten()
+            .filter(|sides| sides.is_visible())
+            .count() as u32
+    }
+
+    pub fn max_scenic_score(&self, rules: &ViewRules) -> u64 {
+        self.scenic_scores(rules)
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn parse_row(line: &str, line_nr: usize, format: ForestFormat) -> Result<Vec<u32>, ForestError> {
+    let trimmed = line.trim();
+    let heights: Vec<&str> = match format {
+        ForestFormat::Digits => trimmed
+            .char_indices()
+            .map(|(index, c)| &trimmed[index..index + c.len_utf8()])
+            .collect(),
+        ForestFormat::Separated(separator) => trimmed.split(separator).map(str::trim).collect(),
+        ForestFormat::Whitespace => trimmed.split_whitespace().collect(),
+    };
+
+    heights
+        .into_iter()
+        .map(|height| {
+            let valid = height.bytes().all(|b| b.is_ascii_digit());
+            match height.parse() {
+                Ok(value) if valid => Ok(value),
+                _ => {
+                    // every height is a slice of the line, so its offset is its position
+                    let offset = height.as_ptr() as usize - line.as_ptr() as usize;
+                    Err(ForestError::InvalidHeight {
+                        height: height.to_string(),
+                        line: line_nr,
+                        column: line[..offset].chars().count() + 1,
+                    })
                 }
             }
+        })
+        .collect()
+}
 
-            if visible_from_left || visible_from_right || visible_from_top || visible_from_bottom {
-                count += 1;
-            }
+/// The sides of the forest a tree is visible from, as bit flags.
+/// With other `ViewRules` every direction gets its own flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sides(u32);
+
+impl Sides {
+    /// the number of directions that have a flag
+    pub const MAX_DIRECTIONS: usize = 32;
+
+    pub const NONE: Sides = Sides(0);
+    pub const LEFT: Sides = Sides(1);
+    pub const RIGHT: Sides = Sides(2);
+    pub const TOP: Sides = Sides(4);
+    pub const BOTTOM: Sides = Sides(8);
+
+    /// the flag of the n-th direction of the `ViewRules`, `None` from `MAX_DIRECTIONS` on
+    pub fn direction(index: usize) -> Option<Sides> {
+        (index < Self::MAX_DIRECTIONS).then(|| Sides(1 << index))
+    }
+
+    pub fn contains(&self, other: Sides) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// visible from at least one side
+    pub fn is_visible(&self) -> bool {
+        self.0 != 0
+    }
+
+    /// the number of sides
+    pub fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+}
+
+impl std::ops::BitOr for Sides {
+    type Output = Sides;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Sides(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Sides {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Exports a map as a plain (ASCII) PGM image, scaled so the highest value is white
+pub fn to_pgm(map: &[Vec<u64>]) -> String {
+    let height = map.len();
+    let width = map.first().map_or(0, |row| row.len());
+    let max = map.iter().flatten().copied().max().unwrap_or(0).max(1) as u128;
+
+    let mut out = format!("P2\n{width} {height}\n255\n");
+    for row in map {
+        let line: Vec<String> = row
+            .iter()
+            .map(|&value| (value as u128 * 255 / max).to_string())
+            .collect();
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders a map as a heatmap with ANSI background colors, from dark blue for
+/// the lowest to red for the highest value. Every tree is two characters wide.
+pub fn to_ansi_heatmap(map: &[Vec<u64>]) -> String {
+    // colors of the 256 color palette
+    const HEAT_COLORS: [u8; 8] = [17, 19, 27, 37, 71, 178, 208, 196];
+    let min = map.iter().flatten().copied().min().unwrap_or(0) as u128;
+    let max = map.iter().flatten().copied().max().unwrap_or(0) as u128;
+    let range = (max - min).max(1);
+
+    let mut out = String::new();
+    for row in map {
+        for &value in row {
+            let index = (value as u128 - min) * (HEAT_COLORS.len() as u128 - 1) / range;
+            let _ = write!(out, "\x1b[48;5;{}m  ", HEAT_COLORS[index as usize]);
         }
+        out.push_str("\x1b[0m\n");
     }
-    count
+    out
 }
 
-pub fn solve_star_two(input: &str) -> u32 {
-    let trees = parse_trees(input);
-    let height = trees.len();
-    let width = trees[0].len();
-
-    let mut max_distance = 0;
-
-    // leave the edges becouse there are anyway zero
-    for y in 1..width - 1 {
-        for x in 1..height - 1 {
-            let mut tree_distances: u32 = 1;
-            let tree = trees[y][x];
-            let mut distance: u32;
-
-            // see to the top
-            distance = 0;
-            for y_top in (0..y).rev() {
-                distance += 1;
-                let line_tree = trees[y_top][x];
-                if line_tree >= tree {
-                    break;
-                }
+/// The lines of sight into the forest, looking from outside into the given direction.
+/// Every line starts at the edge and goes inwards against the direction.
+fn sight_lines(height: usize, width: usize, (dx, dy): (i32, i32)) -> Vec<Vec<(usize, usize)>> {
+    assert!((dx, dy) != (0, 0), "A direction can't be (0, 0)");
+    let inside = |x: i64, y: i64| x >= 0 && y >= 0 && x < width as i64 && y < height as i64;
+    let (dx, dy) = (dx as i64, dy as i64);
+    let mut lines = Vec::new();
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            // a line starts at every tree, that hasn't a tree in front of it
+            if inside(x + dx, y + dy) {
+                continue;
             }
-            tree_distances *= distance;
-
-            // see to the left
-            distance = 0;
-            for x_left in (0..x).rev() {
-                distance += 1;
-                let line_tree = trees[y][x_left];
-                if line_tree >= tree {
-                    break;
-                }
+            let mut line = Vec::new();
+            let (mut cx, mut cy) = (x, y);
+            while inside(cx, cy) {
+                line.push((cx as usize, cy as usize));
+                cx -= dx;
+                cy -= dy;
             }
-            tree_distances *= distance;
-
-            // see to the bottom
-            distance = 0;
-            for y_bot in y + 1..height {
-                distance += 1;
-                let line_tree = trees[y_bot][x];
-                if line_tree >= tree {
-                    break;
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Finds the directions every tree is visible from.
+/// Every line of sight is swept once while remembering the highest tree so far.
+fn visible_grid(trees: &[Vec<u32>], rules: &ViewRules) -> Vec<Vec<Sides>> {
+    let height = trees.len();
+    let width = trees.first().map_or(0, |row| row.len());
+    let mut visible = vec![vec![Sides::NONE; width]; height];
+
+    for (index, &direction) in rules.directions.iter().enumerate() {
+        let side = Sides::direction(index).expect("ViewRules have a flag for every direction");
+        for line in sight_lines(height, width, direction) {
+            let mut highest: Option<u32> = None;
+            for (x, y) in line {
+                let tree = trees[y][x];
+                if highest.is_none_or(|highest| !rules.line_of_sight.blocks(highest, tree)) {
+                    visible[y][x] |= side;
                 }
+                highest = highest.max(Some(tree));
             }
-            tree_distances *= distance;
-
-            // see to the right
-            distance = 0;
-            for x_right in x + 1..width {
-                distance += 1;
-                let line_tree = trees[y][x_right];
-                if line_tree >= tree {
-                    break;
+        }
+    }
+    visible
+}
+
+/// Calculates the scenic score of every tree.
+/// Every line of sight is swept once with a monotonic stack of the trees that could still
+/// block the view, so the tree blocking the view is always on the top of the stack.
+/// With many directions in a large forest the product can exceed 64 bits, then the
+/// score stays at `u64::MAX`.
+fn scenic_scores(trees: &[Vec<u32>], rules: &ViewRules) -> Vec<Vec<u64>> {
+    let height = trees.len();
+    let width = trees.first().map_or(0, |row| row.len());
+    let mut scores = vec![vec![1u64; width]; height];
+
+    for &direction in &rules.directions {
+        for line in sight_lines(height, width, direction) {
+            // the positions in the line of the trees that are still blocking
+            let mut stack: Vec<usize> = Vec::new();
+            for (index, &(x, y)) in line.iter().enumerate() {
+                let tree = trees[y][x];
+                while stack.last().is_some_and(|&last| {
+                    let (last_x, last_y) = line[last];
+                    !rules.line_of_sight.blocks(trees[last_y][last_x], tree)
+                }) {
+                    stack.pop();
                 }
+                // the view ends at the blocking tree or at the edge
+                let distance = match stack.last() {
+                    Some(&blocking) => index - blocking,
+                    None => index,
+                };
+                scores[y][x] = scores[y][x].saturating_mul(distance as u64);
+                stack.push(index);
             }
-            tree_distances *= distance;
-
-            max_distance = max_distance.max(tree_distances);
         }
     }
-    max_distance
+    scores
 }
 
-fn parse_trees(input: &str) -> Vec<Vec<u8>> {
-    input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| c.to_digit(10).unwrap() as u8)
-                .collect()
-        })
-        .collect()
-}
+pub const EXAMPLE: &str = "\
+30373
+25512
+65332
+33549
+35390";
 
 #[cfg(test)]
 mod tests_day_08 {
 
     use crate::load_input;
+    use crate::rng::Rng;
 
     use super::*;
 
@@ -157,11 +483,339 @@ mod tests_day_08 {
         let result = solve_star_two(&load_input(8));
         assert_eq!(result, 172224);
     }
-}
 
-pub const EXAMPLE: &str = "\
-30373
-25512
-65332
-33549
-35390";
+    #[test]
+    fn test_rectangular_forest() {
+        let input = "30373\n25512\n65332";
+        assert_eq!(solve_star_one(input), 14);
+        let forest = Forest::from_str(input).unwrap();
+        assert_eq!(count_visible_brute_force(&forest.trees), 14);
+
+        let input = "3037\n2551\n6533\n3354\n3539";
+        let forest = Forest::from_str(input).unwrap();
+        assert_eq!(
+            solve_star_two(input),
+            max_scenic_score_brute_force(&forest.trees)
+        );
+    }
+
+    #[test]
+    fn test_sweeps_match_brute_force() {
+        let mut rng = Rng::new(8);
+        for _ in 0..200 {
+            let height = rng.below(12) as usize + 3;
+            let width = rng.below(12) as usize + 3;
+            let forest = Forest {
+                trees: (0..height)
+                    .map(|_| (0..width).map(|_| rng.below(10) as u32).collect())
+                    .collect(),
+            };
+
+            let rules = ViewRules::default();
+            let count = forest.count_visible(&rules);
+            assert_eq!(count, count_visible_brute_force(&forest.trees));
+            let max_score = forest.max_scenic_score(&rules);
+            assert_eq!(max_score, max_scenic_score_brute_force(&forest.trees));
+
+            // and the same for other rules
+            let mut directions = ViewRules::with_diagonals().directions().to_vec();
+            directions.push((2, 1));
+            let mut rules = ViewRules::new(directions, LineOfSight::StrictlyShorter).unwrap();
+            for line_of_sight in [LineOfSight::StrictlyShorter, LineOfSight::ShorterOrEqual] {
+                rules.line_of_sight = line_of_sight;
+                let (visibility, scores) = look_around_brute_force(&forest, &rules);
+                assert_eq!(forest.visibility(&rules), visibility);
+                assert_eq!(forest.scenic_scores(&rules), scores);
+            }
+        }
+    }
+
+    /// Looks from every tree into every direction of the rules
+    fn look_around_brute_force(
+        forest: &Forest,
+        rules: &ViewRules,
+    ) -> (Vec<Vec<Sides>>, Vec<Vec<u64>>) {
+        let mut visibility = vec![vec![Sides::NONE; forest.width()]; forest.height()];
+        let mut scores = vec![vec![1; forest.width()]; forest.height()];
+
+        for y in 0..forest.height() {
+            for x in 0..forest.width() {
+                let tree = forest.get(x, y).unwrap();
+                for (index, &(dx, dy)) in rules.directions.iter().enumerate() {
+                    let (mut cx, mut cy) = (x as i32 + dx, y as i32 + dy);
+                    let mut distance = 0;
+                    let mut visible = true;
+                    // a negative coordinate wraps around and is outside too
+                    while let Some(other) = forest.get(cx as usize, cy as usize) {
+                        distance += 1;
+                        if rules.line_of_sight.blocks(other, tree) {
+                            visible = false;
+                            break;
+                        }
+                        cx += dx;
+                        cy += dy;
+                    }
+                    if visible {
+                        visibility[y][x] |= Sides::direction(index).unwrap();
+                    }
+                    scores[y][x] *= distance;
+                }
+            }
+        }
+        (visibility, scores)
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let digits = Forest::from_str(EXAMPLE).unwrap();
+        let separated = Forest::parse(
+            "3,0,3,7,3\n2,5,5,1,2\n6,5,3,3,2\n3,3,5,4,9\n3,5,3,9,0",
+            ForestFormat::Separated(','),
+        )
+        .unwrap();
+        assert_eq!(digits, separated);
+
+        let forest = Forest::parse("10 2 10\n 3 12 4 \n10 2 10", ForestFormat::Whitespace).unwrap();
+        assert_eq!(forest.get(1, 1), Some(12));
+        assert_eq!(forest.count_visible(&ViewRules::default()), 9);
+
+        assert_eq!(
+            Forest::parse("1,2\n\n3", ForestFormat::Separated(',')),
+            Err(ForestError::RaggedRow {
+                line: 3,
+                width: 1,
+                expected: 2
+            })
+        );
+        assert_eq!(
+            Forest::parse("1, a", ForestFormat::Separated(',')),
+            Err(ForestError::InvalidHeight {
+                height: String::from("a"),
+                line: 1,
+                column: 4
+            })
+        );
+        assert_eq!(
+            Forest::parse("0 10\n2 -3", ForestFormat::Whitespace),
+            Err(ForestError::InvalidHeight {
+                height: String::from("-3"),
+                line: 2,
+                column: 3
+            })
+        );
+        assert_eq!(
+            Forest::from_str("12\n3x"),
+            Err(ForestError::InvalidHeight {
+                height: String::from("x"),
+                line: 2,
+                column: 2
+            })
+        );
+        // 0 is a valid height
+        assert_eq!(Forest::from_str("00\n00").unwrap().get(1, 1), Some(0));
+    }
+
+    #[test]
+    fn test_other_rules() {
+        let forest = Forest::from_str(EXAMPLE).unwrap();
+
+        // the left 5 of the second row can only be seen over the other 5 from the right,
+        // if trees of the same height don't block
+        let rules = ViewRules {
+            line_of_sight: LineOfSight::ShorterOrEqual,
+            ..Default::default()
+        };
+        assert!(forest.visibility(&rules)[1][1].contains(Sides::RIGHT));
+        assert!(!forest.visibility(&ViewRules::default())[1][1].contains(Sides::RIGHT));
+        // left 1, right 3, up 1 and down 3
+        assert_eq!(forest.scenic_scores(&rules)[1][1], 9);
+
+        // with diagonals the left 5 of the middle row can be seen from the top left
+        let rules = ViewRules::with_diagonals();
+        assert!(forest.visibility(&rules)[2][1].contains(Sides::direction(4).unwrap()));
+        assert_eq!(forest.scenic_scores(&rules)[3][2], 8 * 2);
+
+        assert_eq!(Sides::direction(32), None);
+        assert_eq!(
+            ViewRules::new(vec![(1, 0); 33], LineOfSight::StrictlyShorter),
+            None
+        );
+        assert_eq!(
+            ViewRules::new(vec![(0, 0)], LineOfSight::StrictlyShorter),
+            None
+        );
+        assert!(ViewRules::new(vec![(1, 0); 32], LineOfSight::StrictlyShorter).is_some());
+    }
+
+    /// Checks every tree by looking into all four directions, used to verify the sweeps
+    fn count_visible_brute_force(trees: &[Vec<u32>]) -> u32 {
+        let height = trees.len();
+        let width = trees[0].len();
+        let mut count: u32 = (2 * height + 2 * width - 4) as u32;
+
+        for y in 1..(height - 1) {
+            for x in 1..(width - 1) {
+                let tree_height = trees[y][x];
+
+                // we assume the tree is visible from all sides and correct that in this interation
+                let mut visible_from_left = true;
+                let mut visible_from_right = true;
+                let mut visible_from_top = true;
+                let mut visible_from_bottom = true;
+
+                for row in &trees[..y] {
+                    if row[x] >= tree_height {
+                        visible_from_top = false;
+                        break;
+                    }
+                }
+
+                for row in &trees[y + 1..height] {
+                    if row[x] >= tree_height {
+                        visible_from_bottom = false;
+                        break;
+                    }
+                }
+
+                for &other in &trees[y][..x] {
+                    if other >= tree_height {
+                        visible_from_left = false;
+                        break;
+                    }
+                }
+
+                for &other in &trees[y][x + 1..width] {
+                    if other >= tree_height {
+                        visible_from_right = false;
+                        break;
+                    }
+                }
+
+                if visible_from_left
+                    || visible_from_right
+                    || visible_from_top
+                    || visible_from_bottom
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Looks from every tree into all four directions, used to verify the sweeps
+    fn max_scenic_score_brute_force(trees: &[Vec<u32>]) -> u64 {
+        let height = trees.len();
+        let width = trees[0].len();
+
+        let mut max_distance = 0;
+
+        // leave the edges becouse there are anyway zero
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let mut tree_distances: u64 = 1;
+                let tree = trees[y][x];
+                let mut distance: u64;
+
+                // see to the top
+                distance = 0;
+                for y_top in (0..y).rev() {
+                    distance += 1;
+                    let line_tree = trees[y_top][x];
+                    if line_tree >= tree {
+                        break;
+                    }
+                }
+                tree_distances *= distance;
+
+                // see to the left
+                distance = 0;
+                for x_left in (0..x).rev() {
+                    distance += 1;
+                    let line_tree = trees[y][x_left];
+                    if line_tree >= tree {
+                        break;
+                    }
+                }
+                tree_distances *= distance;
+
+                // see to the bottom
+                distance = 0;
+                for row in &trees[y + 1..height] {
+                    distance += 1;
+                    let line_tree = row[x];
+                    if line_tree >= tree {
+                        break;
+                    }
+                }
+                tree_distances *= distance;
+
+                // see to the right
+                distance = 0;
+                for &line_tree in &trees[y][x + 1..width] {
+                    distance += 1;
+                    if line_tree >= tree {
+                        break;
+                    }
+                }
+                tree_distances *= distance;
+
+                max_distance = max_distance.max(tree_distances);
+            }
+        }
+        max_distance
+    }
+
+    #[test]
+    fn test_example_maps() {
+        let map = visibility_map(EXAMPLE);
+        assert_eq!(map[0][0], Sides::LEFT | Sides::TOP);
+        assert_eq!(map[2][3], Sides::RIGHT);
+        assert_eq!(map[2][2], Sides::NONE);
+        assert!(map[1][2].contains(Sides::TOP));
+        assert_eq!(map[4][4].count(), 2);
+
+        let scores = scenic_score_map(EXAMPLE);
+        assert_eq!(scores[1][2], 4);
+        assert_eq!(scores[3][2], 8);
+        assert_eq!(scores[0][0], 0);
+        assert_eq!(best_spot(EXAMPLE), Some((2, 3, 8)));
+        assert_eq!(best_spot(""), None);
+    }
+
+    #[test]
+    fn test_scores_beyond_32_bits() {
+        // a tall tree in the middle of 41 x 41 trees sees 20 trees in all 8 directions
+        let mut input = vec![vec!['1'; 41]; 41];
+        input[20][20] = '9';
+        let input: Vec<String> = input.into_iter().map(String::from_iter).collect();
+        let forest = Forest::from_str(&input.join("\n")).unwrap();
+        let rules = ViewRules::with_diagonals();
+        assert_eq!(forest.max_scenic_score(&rules), 20u64.pow(8));
+        let (_, scores) = look_around_brute_force(&forest, &rules);
+        assert_eq!(forest.scenic_scores(&rules), scores);
+
+        // 32 directions in a larger forest don't fit into 64 bits anymore
+        let directions = (1..=4)
+            .flat_map(|dx| (-4..4).map(move |dy| (dx, dy)))
+            .collect();
+        let rules = ViewRules::new(directions, LineOfSight::StrictlyShorter).unwrap();
+        let forest = Forest {
+            trees: (0..101)
+                .map(|y| (0..101).map(|x| (x == 50 && y == 50) as u32).collect())
+                .collect(),
+        };
+        assert_eq!(forest.max_scenic_score(&rules), u64::MAX);
+    }
+
+    #[test]
+    fn test_heatmap_exports() {
+        let map = vec![vec![0, 4], vec![8, 2]];
+        assert_eq!(to_pgm(&map), "P2\n2 2\n255\n0 127\n255 63\n");
+
+        let heatmap = to_ansi_heatmap(&map);
+        assert_eq!(heatmap.lines().count(), 2);
+        assert!(heatmap.starts_with("\x1b[48;5;17m  \x1b[48;5;37m  \x1b[0m\n"));
+        assert!(heatmap.contains("\x1b[48;5;196m"));
+    }
+}
@@ -1,46 +1,209 @@
 #![doc = include_str!("descriptions/day_09.md")]
-use std::{collections::HashSet, hash::Hash};
+use std::collections::HashSet;
+use std::str::FromStr;
 
-#[derive(Debug)]
-enum Direction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     Right,
     Left,
     Up,
     Down,
+    UpRight,
+    UpLeft,
+    DownRight,
+    DownLeft,
     None,
 }
 
-impl Direction {
-    fn from_str(s: &str) -> Result<Self, &'static str> {
-        match s {
-            "R" => Ok(Direction::Right),
-            "L" => Ok(Direction::Left),
-            "U" => Ok(Direction::Up),
-            "D" => Ok(Direction::Down),
+impl FromStr for Direction {
+    type Err = &'static str;
+
+    /// Accepts the puzzle letters, diagonals like `UR` or `RU` and the full names
+    /// like `up` or `down-left`, without caring about the case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().replace(['-', '_'], "").as_str() {
+            "R" | "RIGHT" => Ok(Direction::Right),
+            "L" | "LEFT" => Ok(Direction::Left),
+            "U" | "UP" => Ok(Direction::Up),
+            "D" | "DOWN" => Ok(Direction::Down),
+            "UR" | "RU" | "UPRIGHT" => Ok(Direction::UpRight),
+            "UL" | "LU" | "UPLEFT" => Ok(Direction::UpLeft),
+            "DR" | "RD" | "DOWNRIGHT" => Ok(Direction::DownRight),
+            "DL" | "LD" | "DOWNLEFT" => Ok(Direction::DownLeft),
             _ => Err("Could not wrap Direction"),
         }
     }
 }
 
+impl Direction {
+    /// The change of the position as `(x, y)`, with `y` going up
+    pub fn step(&self) -> (i32, i32) {
+        match self {
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::UpRight => (1, 1),
+            Direction::UpLeft => (-1, 1),
+            Direction::DownRight => (1, -1),
+            Direction::DownLeft => (-1, -1),
+            Direction::None => (0, 0),
+        }
+    }
+
+    /// The direction of a step, only the signs of `x` and `y` matter
+    pub fn from_step(x: i32, y: i32) -> Self {
+        match (x.signum(), y.signum()) {
+            (1, 0) => Direction::Right,
+            (-1, 0) => Direction::Left,
+            (0, 1) => Direction::Up,
+            (0, -1) => Direction::Down,
+            (1, 1) => Direction::UpRight,
+            (-1, 1) => Direction::UpLeft,
+            (1, -1) => Direction::DownRight,
+            (-1, -1) => Direction::DownLeft,
+            _ => Direction::None,
+        }
+    }
+}
+
+/// One move of the head in a motion script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// steps into one direction, like `R 4` or `UR 2`
+    Steps(Direction, u32),
+    /// a relative vector like `V 3 -2`, the head goes diagonal until it is in line
+    /// with the target and then straight, with single steps until it reaches the target
+    Vector(i32, i32),
+}
+
+impl Motion {
+    /// The single steps of the head for this motion, one at a time
+    pub fn directions(&self) -> Directions {
+        Directions { remaining: *self }
+    }
+}
+
+/// Iterator over the single steps of a motion, see `Motion::directions`
+#[derive(Debug, Clone)]
+pub struct Directions {
+    /// the part of the motion that is still ahead
+    remaining: Motion,
+}
+
+impl Iterator for Directions {
+    type Item = Direction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.remaining {
+            Motion::Steps(_, 0) | Motion::Vector(0, 0) => None,
+            Motion::Steps(direction, times) => {
+                *times -= 1;
+                Some(*direction)
+            }
+            Motion::Vector(x, y) => {
+                let direction = Direction::from_step(*x, *y);
+                let (dx, dy) = direction.step();
+                *x -= dx;
+                *y -= dy;
+                Some(direction)
+            }
+        }
+    }
+}
+
+impl FromStr for Motion {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        match parts[..] {
+            [v, x, y] if v.eq_ignore_ascii_case("V") => {
+                let x = x
+                    .parse()
+                    .map_err(|_| "The x of a vector has to be a number")?;
+                let y = y
+                    .parse()
+                    .map_err(|_| "The y of a vector has to be a number")?;
+                Ok(Motion::Vector(x, y))
+            }
+            [direction, times] => {
+                let direction = Direction::from_str(direction)?;
+                let times = times
+                    .parse()
+                    .map_err(|_| "The steps have to be a positive number")?;
+                Ok(Motion::Steps(direction, times))
+            }
+            _ => Err("A motion is a direction with steps or a vector like 'V 3 -2'"),
+        }
+    }
+}
+
+/// A motion script that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MotionError {
+    /// the 1-based line number
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for MotionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for MotionError {}
+
+/// Parses a motion script. Besides the puzzle input it supports
+/// - diagonals and full names like `UR 2` or `down 3`
+/// - vectors like `V 3 -2`
+/// - several motions in one line separated by `;`, like `R 4; U 2`
+/// - comments starting with `#` and empty lines
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_09::{parse_motions, Direction, Motion};
+///
+/// let motions = parse_motions("R 4 # to the right\nUR 2; V 3 -2").unwrap();
+/// assert_eq!(
+///     motions,
+///     vec![
+///         Motion::Steps(Direction::Right, 4),
+///         Motion::Steps(Direction::UpRight, 2),
+///         Motion::Vector(3, -2),
+///     ]
+/// );
+/// ```
+pub fn parse_motions(input: &str) -> Result<Vec<Motion>, MotionError> {
+    let mut motions = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        for part in line.split(';').filter(|part| !part.trim().is_empty()) {
+            let motion = Motion::from_str(part).map_err(|reason| MotionError {
+                line: index + 1,
+                reason,
+            })?;
+            motions.push(motion);
+        }
+    }
+    Ok(motions)
+}
+
 #[derive(Hash, Eq, PartialEq, Clone, Copy, Debug)]
-struct Position {
-    x: i32,
-    y: i32,
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
 }
 
 impl Position {
-    fn build(x: i32, y: i32) -> Self {
+    pub fn build(x: i32, y: i32) -> Self {
         Self { x, y }
     }
 
     fn movement(&mut self, dir: &Direction) {
-        match dir {
-            Direction::Left => self.x -= 1,
-            Direction::Right => self.x += 1,
-            Direction::Up => self.y += 1,
-            Direction::Down => self.y -= 1,
-            Direction::None => {}
-        };
+        let (x, y) = dir.step();
+        self.x += x;
+        self.y += y;
     }
 
     fn is_far_away(&self, other: &Position) -> bool {
@@ -69,58 +232,253 @@ impl Position {
 }
 
 pub fn solve_star_one(input: &str) -> u32 {
-    // setup the positions
-    let mut head_position = Position::build(0, 0);
-    let mut tail_position = Position::build(0, 0);
-    let mut path: HashSet<Position> = HashSet::new();
-    // collect instructions
-    let instructions: Vec<(Direction, u32)> = input.lines().map(|line| parse_line(line)).collect();
+    simulate(input, 2)
+}
+
+pub fn solve_star_two(input: &str) -> u32 {
+    simulate(input, 10)
+}
+
+/// Moves a rope with the given number of knots and counts the positions the tail visited
+fn simulate(input: &str, knots: usize) -> u32 {
+    let mut rope = Rope::new(knots);
+    for motion in parse_motions(input).expect("The motions are invalid") {
+        rope.apply_motion(&motion);
+    }
+    rope.visited(knots - 1).len() as u32
+}
+
+/// A rope with a fixed number of knots, the first knot is the head
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_09::{Direction, Position, Rope};
+///
+/// let mut rope = Rope::new(3);
+/// rope.apply(&Direction::Right, 4);
+/// assert_eq!(rope.head(), Position::build(4, 0));
+/// assert_eq!(rope.knots()[1], Position::build(3, 0));
+/// assert_eq!(rope.tail(), Position::build(2, 0));
+/// assert_eq!(rope.visited(2).len(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<Position>,
+    visited: Vec<HashSet<Position>>,
+}
+
+impl Rope {
+    /// Creates a rope with all knots at the start `(0, 0)`
+    pub fn new(knots: usize) -> Self {
+        assert!(knots > 0, "A rope needs at least a head");
+        let start = Position::build(0, 0);
+        Self {
+            knots: vec![start; knots],
+            visited: vec![HashSet::from([start]); knots],
+        }
+    }
 
-    // save the initial position
-    path.insert(tail_position.clone());
+    /// Moves the head one step and lets every other knot follow
+    pub fn step(&mut self, direction: &Direction) {
+        self.knots[0].movement(direction);
+        for index in 1..self.knots.len() {
+            let prev = self.knots[index - 1];
+            tail_movement(&prev, &mut self.knots[index]);
+        }
+        for (knot, visited) in self.knots.iter().zip(&mut self.visited) {
+            visited.insert(*knot);
+        }
+    }
 
-    for (direction, times) in &instructions {
-        for _ in 0..*times {
-            head_position.movement(&direction);
+    /// Moves the head the given times into a direction
+    pub fn apply(&mut self, direction: &Direction, times: u32) {
+        for _ in 0..times {
+            self.step(direction);
+        }
+    }
 
-            tail_movement(&head_position, &mut tail_position);
-            // save the tail position in the set (unique)
-            path.insert(tail_position.clone());
+    /// Moves the head step by step through a motion
+    pub fn apply_motion(&mut self, motion: &Motion) {
+        for direction in motion.directions() {
+            self.step(&direction);
         }
     }
-    path.len() as u32
+
+    pub fn knots(&self) -> &[Position] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Position {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Position {
+        self.knots[self.knots.len() - 1]
+    }
+
+    /// All positions a knot has been at, including the start
+    pub fn visited(&self, knot: usize) -> &HashSet<Position> {
+        &self.visited[knot]
+    }
+
+    /// The label of a knot like in the puzzle: `H` for the head, `T` for the tail of
+    /// a rope with two knots and otherwise the number of the knot (letters after 9)
+    pub fn label(&self, knot: usize) -> char {
+        match knot {
+            0 => 'H',
+            1 if self.knots.len() == 2 => 'T',
+            n => char::from_digit(n as u32 % 36, 36).unwrap_or('?'),
+        }
+    }
+
+    /// Renders the current state inside the area everything has visited so far
+    pub fn render(&self) -> String {
+        let (min, max) = bounding_box(self.visited.iter().flatten())
+            .expect("A rope has always visited the start");
+        self.render_in(min, max)
+    }
+
+    /// Renders the current state inside the area from `min` to `max`.
+    /// A knot in front covers the knots behind it, like `H` covers `1`.
+    pub fn render_in(&self, min: Position, max: Position) -> String {
+        let start = Position::build(0, 0);
+        render_grid(min, max, |position| {
+            match self.knots.iter().position(|&knot| knot == position) {
+                Some(knot) => self.label(knot),
+                None if position == start => 's',
+                None => '.',
+            }
+        })
+    }
 }
 
-pub fn solve_star_two(input: &str) -> u32 {
-    // setup positions
-    let mut head_position = Position::build(0, 0);
-    let mut rope: Vec<Position> = vec![head_position.clone(); 9];
-    let mut path: HashSet<Position> = HashSet::new();
+/// The smallest area `(min, max)` that contains all positions
+pub fn bounding_box<'a>(
+    positions: impl IntoIterator<Item = &'a Position>,
+) -> Option<(Position, Position)> {
+    positions.into_iter().fold(None, |area, position| {
+        let (min, max) = area.unwrap_or((*position, *position));
+        Some((
+            Position::build(min.x.min(position.x), min.y.min(position.y)),
+            Position::build(max.x.max(position.x), max.y.max(position.y)),
+        ))
+    })
+}
 
-    // collect instructions
-    let instructions: Vec<(Direction, u32)> = input.lines().map(|line| parse_line(line)).collect();
+/// Renders visited positions with `#` and the start with `s`, like the puzzle
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_09::{render_visited, Direction, Rope};
+///
+/// let mut rope = Rope::new(2);
+/// rope.apply(&Direction::Right, 3);
+/// rope.apply(&Direction::Up, 2);
+/// assert_eq!(render_visited(rope.visited(1)), "...#\ns##.\n");
+/// ```
+pub fn render_visited(visited: &HashSet<Position>) -> String {
+    let start = Position::build(0, 0);
+    let Some((min, max)) = bounding_box(visited.iter().chain([&start])) else {
+        return String::new();
+    };
+    render_grid(min, max, |position| match position {
+        p if p == start => 's',
+        p if visited.contains(&p) => '#',
+        _ => '.',
+    })
+}
 
-    // save the initial position
-    path.insert(head_position.clone());
+/// Renders the rope after every step, all frames with the same area
+pub fn render_steps(input: &str, knots: usize) -> Vec<String> {
+    let motions = parse_motions(input).expect("The motions are invalid");
 
-    for (direction, times) in &instructions {
-        for _ in 0..*times {
-            head_position.movement(&direction); // move the head as before
+    // the first run finds the area, the second one draws the frames
+    let mut rope = Rope::new(knots);
+    for motion in &motions {
+        rope.apply_motion(motion);
+    }
+    let (min, max) = bounding_box(rope.visited.iter().flatten()).unwrap();
 
-            let mut prev: Option<&Position> = Some(&head_position); // set initial the previev knot as head
+    let mut rope = Rope::new(knots);
+    let mut frames = vec![rope.render_in(min, max)];
+    for motion in &motions {
+        for direction in motion.directions() {
+            rope.step(&direction);
+            frames.push(rope.render_in(min, max));
+        }
+    }
+    frames
+}
 
-            // loop threw the tails/knots of the rope
-            for knot in &mut rope {
-                if let Some(prev) = prev {
-                    tail_movement(prev, knot);
-                }
-                prev = Some(knot)
+/// Exports the simulation as an animated SVG. Every knot moves on its own and the
+/// cells visited by the tail show up over time, so long move lists stay small.
+pub fn to_animated_svg(input: &str, knots: usize, seconds_per_step: f32) -> String {
+    const CELL: i32 = 10;
+    let mut rope = Rope::new(knots);
+    let mut history: Vec<Vec<Position>> = vec![rope.knots.clone()];
+    // the tail cells in the order they were visited the first time, with the step
+    let mut trail: Vec<(usize, Position)> = vec![(0, rope.tail())];
+    for motion in parse_motions(input).expect("The motions are invalid") {
+        for direction in motion.directions() {
+            let known = rope.visited(knots - 1).len();
+            rope.step(&direction);
+            if rope.visited(knots - 1).len() > known {
+                trail.push((history.len(), rope.tail()));
             }
-            // save the tail of the rope in the path
-            path.insert(rope[8].clone());
+            history.push(rope.knots.clone());
+        }
+    }
+
+    let (min, max) = bounding_box(rope.visited.iter().flatten()).unwrap();
+    let to_svg = |position: Position| ((position.x - min.x) * CELL, (max.y - position.y) * CELL);
+    let width = (max.x - min.x + 1) * CELL;
+    let height = (max.y - min.y + 1) * CELL;
+    let duration = history.len() as f32 * seconds_per_step;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n"
+    );
+    for (step, position) in trail {
+        let (x, y) = to_svg(position);
+        svg.push_str(&format!(
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{CELL}\" height=\"{CELL}\" fill=\"lightgray\" \
+             visibility=\"hidden\"><set attributeName=\"visibility\" to=\"visible\" \
+             begin=\"{}s\" fill=\"freeze\"/></rect>\n",
+            step as f32 * seconds_per_step
+        ));
+    }
+    // draw the tail first, so the head is on top
+    for knot in (0..knots).rev() {
+        let (xs, ys): (Vec<String>, Vec<String>) = history
+            .iter()
+            .map(|knots| {
+                let (x, y) = to_svg(knots[knot]);
+                (x.to_string(), (y + CELL - 1).to_string())
+            })
+            .unzip();
+        svg.push_str(&format!(
+            "<text font-family=\"monospace\" font-size=\"{CELL}\">{}\
+             <animate attributeName=\"x\" values=\"{}\" dur=\"{duration}s\" calcMode=\"discrete\" fill=\"freeze\"/>\
+             <animate attributeName=\"y\" values=\"{}\" dur=\"{duration}s\" calcMode=\"discrete\" fill=\"freeze\"/>\
+             </text>\n",
+            rope.label(knot),
+            xs.join(";"),
+            ys.join(";")
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Renders a grid from the top (highest `y`) to the bottom, one line per row
+fn render_grid(min: Position, max: Position, cell: impl Fn(Position) -> char) -> String {
+    let mut out = String::new();
+    for y in (min.y..=max.y).rev() {
+        for x in min.x..=max.x {
+            out.push(cell(Position::build(x, y)));
         }
+        out.push('\n');
     }
-    path.len() as u32
+    out
 }
 
 /// Handles the tail movement
@@ -135,14 +493,6 @@ fn tail_movement(head_position: &Position, tail_position: &mut Position) {
     tail_position.movement(&direction_2);
 }
 
-/// Parse the line in the direction and times
-fn parse_line(line: &str) -> (Direction, u32) {
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    let direction = Direction::from_str(parts[0]).unwrap();
-    let times: u32 = parts.get(1).unwrap().parse().unwrap();
-    (direction, times)
-}
-
 pub const EXAMPLE: &str = "\
 R 4
 U 4
@@ -178,4 +528,158 @@ mod tests_day_09 {
         let r = solve_star_two(&input);
         assert_eq!(r, 2653);
     }
+
+    #[test]
+    fn test_example_star_two() {
+        let larger = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+        assert_eq!(solve_star_two(EXAMPLE), 1);
+        assert_eq!(solve_star_two(larger), 36);
+    }
+
+    #[test]
+    fn test_rope_knots() {
+        let mut rope = Rope::new(10);
+        for motion in parse_motions(EXAMPLE).unwrap() {
+            rope.apply_motion(&motion);
+        }
+        // the final state of the example
+        assert_eq!(rope.head(), Position::build(2, 2));
+        assert_eq!(rope.knots()[1], Position::build(1, 2));
+        assert_eq!(rope.knots()[3], Position::build(3, 2));
+        assert_eq!(rope.knots()[4], Position::build(2, 2));
+        assert_eq!(rope.knots()[5], Position::build(1, 1));
+        assert_eq!(rope.knots()[6], Position::build(0, 0));
+        assert_eq!(rope.tail(), Position::build(0, 0));
+
+        // the visited positions of the second knot are the tail positions of part one
+        assert_eq!(rope.visited(1).len(), 13);
+        assert_eq!(rope.visited(9).len(), 1);
+    }
+
+    #[test]
+    fn test_render_example() {
+        let mut rope = Rope::new(2);
+        for motion in parse_motions(EXAMPLE).unwrap() {
+            rope.apply_motion(&motion);
+        }
+        let expected = "\
+..##.
+...##
+.####
+....#
+s###.
+";
+        assert_eq!(render_visited(rope.visited(1)), expected);
+
+        // the final state of part two, inside the area of the puzzle
+        let mut rope = Rope::new(10);
+        for motion in parse_motions(EXAMPLE).unwrap() {
+            rope.apply_motion(&motion);
+        }
+        let expected = "\
+......
+......
+.1H3..
+.5....
+6.....
+";
+        let area = (Position::build(0, 0), Position::build(5, 4));
+        assert_eq!(rope.render_in(area.0, area.1), expected);
+    }
+
+    #[test]
+    fn test_render_steps() {
+        let frames = render_steps(EXAMPLE, 2);
+        assert_eq!(frames.len(), 25);
+        assert_eq!(frames[0], "......\n......\n......\n......\nH.....\n");
+        assert_eq!(frames[1], "......\n......\n......\n......\nTH....\n");
+        assert_eq!(frames[2], "......\n......\n......\n......\nsTH...\n");
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let positions = [Position::build(3, -2), Position::build(-1, 4)];
+        assert_eq!(
+            bounding_box(&positions),
+            Some((Position::build(-1, -2), Position::build(3, 4)))
+        );
+        assert_eq!(bounding_box(&[]), None);
+    }
+
+    #[test]
+    fn test_animated_svg() {
+        let svg = to_animated_svg(EXAMPLE, 10, 0.1);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // one text per knot with one value per frame
+        assert_eq!(svg.matches("<text ").count(), 10);
+        let values = svg
+            .split("values=\"")
+            .nth(1)
+            .unwrap()
+            .split('"')
+            .next()
+            .unwrap();
+        assert_eq!(values.split(';').count(), 25);
+        // the tail of the example never leaves the start
+        assert_eq!(svg.matches("<set ").count(), 1);
+    }
+
+    #[test]
+    fn test_motion_script() {
+        let script = "\
+# the puzzle format still works
+R 4
+up 2; ur 1
+V -3 1 # a vector
+
+DL 2";
+        let motions = parse_motions(script).unwrap();
+        assert_eq!(
+            motions,
+            vec![
+                Motion::Steps(Direction::Right, 4),
+                Motion::Steps(Direction::Up, 2),
+                Motion::Steps(Direction::UpRight, 1),
+                Motion::Vector(-3, 1),
+                Motion::Steps(Direction::DownLeft, 2),
+            ]
+        );
+
+        let mut rope = Rope::new(2);
+        for motion in &motions {
+            rope.apply_motion(motion);
+        }
+        assert_eq!(rope.head(), Position::build(0, 2));
+
+        assert_eq!(
+            parse_motions("R 4\nX 2"),
+            Err(MotionError {
+                line: 2,
+                reason: "Could not wrap Direction"
+            })
+        );
+        assert!(parse_motions("V 1").is_err());
+        assert!(parse_motions("R -1").is_err());
+    }
+
+    #[test]
+    fn test_vector_steps() {
+        assert_eq!(
+            Motion::Vector(3, -2).directions().collect::<Vec<_>>(),
+            vec![Direction::DownRight, Direction::DownRight, Direction::Right]
+        );
+        assert_eq!(Motion::Vector(0, 0).directions().next(), None);
+
+        // the steps are produced one by one, a huge motion doesn't need any memory
+        let mut steps = Motion::Steps(Direction::Left, 4_000_000_000).directions();
+        assert_eq!(steps.next(), Some(Direction::Left));
+        assert_eq!(steps.nth(3_000_000), Some(Direction::Left));
+
+        // a diagonal head drags the tail diagonal too
+        let mut rope = Rope::new(2);
+        rope.apply(&Direction::UpRight, 3);
+        assert_eq!(rope.tail(), Position::build(2, 2));
+        assert_eq!(rope.visited(1).len(), 3);
+    }
 }
@@ -1,160 +1,1053 @@
 #![doc = include_str!("descriptions/day_10.md")]
 
-use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
-const CRT_DIMENSIONS: (usize, usize) = (40, 6);
+/// One of the registers `a` to `z` of the CPU, the puzzle only uses `x`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(u8);
 
-enum Instruction {
-    Add(i32),
+impl Register {
+    pub const X: Register = Register(b'x' - b'a');
+
+    /// the register with the given name from `a` to `z`
+    pub fn from_name(name: char) -> Option<Self> {
+        name.is_ascii_lowercase()
+            .then(|| Register(name as u8 - b'a'))
+    }
+
+    pub fn name(&self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+/// The values of all registers. `x` starts with 1, all others with 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers([i32; 26]);
+
+impl Default for Registers {
+    fn default() -> Self {
+        let mut values = [0; 26];
+        values[Register::X.0 as usize] = 1;
+        Self(values)
+    }
+}
+
+impl Registers {
+    pub fn get(&self, register: Register) -> i32 {
+        self.0[register.0 as usize]
+    }
+
+    pub fn set(&mut self, register: Register, value: i32) {
+        self.0[register.0 as usize] = value;
+    }
+
+    /// the value of the `x` register
+    pub fn x(&self) -> i32 {
+        self.get(Register::X)
+    }
+}
+
+/// The instructions of the CPU.
+///
+/// A new opcode needs a variant here, its cost in `cycles`, its effect in `execute`
+/// and its text in `from_str` and `fmt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// `add<r> V` adds `V` to the register `r`, e.g. `addx 3`
+    Add(Register, i32),
+    /// `noop` does nothing
     Noop,
 }
 
+impl Instruction {
+    /// the number of cycles the instruction takes to complete
+    pub fn cycles(&self) -> u32 {
+        match self {
+            Instruction::Add(..) => 2,
+            Instruction::Noop => 1,
+        }
+    }
+
+    /// applies the instruction after its last cycle
+    pub fn execute(&self, registers: &mut Registers) {
+        match *self {
+            Instruction::Add(register, value) => {
+                registers.set(register, registers.get(register) + value)
+            }
+            Instruction::Noop => (),
+        }
+    }
+}
+
 impl FromStr for Instruction {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.split_whitespace().collect();
-        match parts[0] {
-            "addx" => Ok(Instruction::Add(
-                parts[1].parse::<i32>().expect("Invalid number"),
-            )),
-            "noop" => Ok(Instruction::Noop),
+        match parts[..] {
+            ["noop"] => Ok(Instruction::Noop),
+            [opcode, value] if opcode.len() == 4 && opcode.starts_with("add") => {
+                let register = opcode
+                    .chars()
+                    .last()
+                    .and_then(Register::from_name)
+                    .ok_or("Invalid register")?;
+                let value = value.parse::<i32>().map_err(|_| "Invalid number")?;
+                Ok(Instruction::Add(register, value))
+            }
             _ => Err("Invalid format"),
         }
     }
 }
 
-struct CPU {
-    register: i32,
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Add(register, value) => write!(f, "add{} {value}", register.name()),
+            Instruction::Noop => write!(f, "noop"),
+        }
+    }
 }
 
-impl CPU {
-    fn new() -> Self {
-        Self { register: 1 }
+/// What happened in one cycle of the CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// the number of the cycle, starting with 1
+    pub cycle: usize,
+    /// the instruction that is running in this cycle
+    pub instruction: Instruction,
+    /// the registers during the cycle
+    pub during: Registers,
+    /// the registers after the cycle, changed if the instruction completed
+    pub after: Registers,
+    /// the instruction completed in this cycle
+    pub completed: bool,
+}
+
+/// A cycle accurate CPU running a program
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::{parse_program, Cpu};
+///
+/// let mut cpu = Cpu::new(parse_program("noop\naddx 3\naddx -5").unwrap());
+/// let x: Vec<(i32, i32)> = std::iter::from_fn(|| cpu.tick())
+///     .map(|tick| (tick.during.x(), tick.after.x()))
+///     .collect();
+/// assert_eq!(x, [(1, 1), (1, 1), (1, 4), (4, 4), (4, -1)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cpu {
+    registers: Registers,
+    program: Vec<Instruction>,
+    /// the index of the running instruction
+    pointer: usize,
+    /// the cycles the running instruction already took
+    elapsed: u32,
+    cycle: usize,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            registers: Registers::default(),
+            program,
+            pointer: 0,
+            elapsed: 0,
+            cycle: 0,
+        }
+    }
+
+    /// Runs one cycle, `None` if the program has ended
+    pub fn tick(&mut self) -> Option<Tick> {
+        let instruction = *self.program.get(self.pointer)?;
+        let during = self.registers;
+        self.cycle += 1;
+        self.elapsed += 1;
+
+        let completed = self.elapsed >= instruction.cycles();
+        if completed {
+            instruction.execute(&mut self.registers);
+            self.pointer += 1;
+            self.elapsed = 0;
+        }
+
+        Some(Tick {
+            cycle: self.cycle,
+            instruction,
+            during,
+            after: self.registers,
+            completed,
+        })
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
     }
 
-    fn compute(&mut self, instruction: Option<&Instruction>) {
-        if let Some(la_instruction) = instruction {
-            match la_instruction {
-                Instruction::Add(value) => self.register += value,
-                Instruction::Noop => (),
+    /// the number of cycles that already ran
+    pub fn cycle(&self) -> usize {
+        self.cycle
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.pointer >= self.program.len()
+    }
+}
+
+/// Parses a program with one instruction per line, empty lines are skipped
+pub fn parse_program(input: &str) -> Result<Vec<Instruction>, &'static str> {
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Instruction::from_str)
+        .collect()
+}
+
+/// A CRT that draws one pixel per cycle, left to right and top to bottom.
+/// A pixel is lit if the sprite, centered on the `x` register, covers it.
+/// The default is the 40×6 screen of the puzzle with a sprite 3 pixels wide.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::{parse_program, Cpu, Crt};
+///
+/// let mut crt = Crt::new(4, 1).with_glyphs('█', ' ');
+/// crt.run(Cpu::new(parse_program("addx 5\nnoop\nnoop").unwrap()));
+/// assert_eq!(crt.render(), "██  ");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+    lit: char,
+    dark: char,
+    pixels: Vec<bool>,
+    /// the index of the pixel that is drawn next
+    position: usize,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Self::new(40, 6)
+    }
+}
+
+impl Crt {
+    /// A dark screen with a sprite 3 pixels wide, drawn with `#` and `.`
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "A CRT needs at least one pixel");
+        Self {
+            width,
+            height,
+            sprite_width: 3,
+            lit: '#',
+            dark: '.',
+            pixels: vec![false; width * height],
+            position: 0,
+        }
+    }
+
+    pub fn with_sprite_width(mut self, sprite_width: usize) -> Self {
+        self.sprite_width = sprite_width;
+        self
+    }
+
+    /// The characters for lit and dark pixels in `render` and `rows`
+    pub fn with_glyphs(mut self, lit: char, dark: char) -> Self {
+        self.lit = lit;
+        self.dark = dark;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Checks if the sprite at `sprite` covers the column. A sprite with an
+    /// even width reaches one pixel further to the right than to the left.
+    pub fn sprite_covers(&self, column: usize, sprite: i32) -> bool {
+        let left = sprite as i64 - (self.sprite_width as i64 - 1) / 2;
+        let column = column as i64;
+        column >= left && column < left + self.sprite_width as i64
+    }
+
+    /// Draws the next pixel with the sprite at `sprite` and returns its position
+    /// `(x, y)` and if it is lit. After the last pixel it starts again at the top.
+    pub fn draw(&mut self, sprite: i32) -> ((usize, usize), bool) {
+        let pixel = (self.position % self.width, self.position / self.width);
+        let lit = self.sprite_covers(pixel.0, sprite);
+        self.pixels[self.position] = lit;
+        self.position = (self.position + 1) % self.pixels.len();
+        (pixel, lit)
+    }
+
+    /// Draws one pixel for every cycle until the program ends
+    pub fn run(&mut self, mut cpu: Cpu) {
+        while let Some(tick) = cpu.tick() {
+            self.draw(tick.during.x());
+        }
+    }
+
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[y * self.width + x]
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        self.pixels
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&lit| if lit { self.lit } else { self.dark })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn render(&self) -> String {
+        self.rows().join("\n")
+    }
+
+    /// Exports the screen as a plain (ASCII) PBM image, lit pixels are black
+    pub fn to_pbm(&self) -> String {
+        let mut out = format!("P1\n{} {}\n", self.width, self.height);
+        for row in self.pixels.chunks(self.width) {
+            let line: Vec<&str> = row.iter().map(|&lit| if lit { "1" } else { "0" }).collect();
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the screen with Unicode half blocks, two rows per line
+    pub fn to_blocks(&self) -> String {
+        let mut lines = Vec::new();
+        for y in (0..self.height).step_by(2) {
+            let line: String = (0..self.width)
+                .map(|x| match (self.is_lit(x, y), self.is_lit(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect();
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// One cycle in the trace of the `Debugger`, with the pixel the CRT drew
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub tick: Tick,
+    /// the position `(x, y)` of the drawn pixel on the CRT
+    pub pixel: (usize, usize),
+    pub lit: bool,
+}
+
+/// How a register is compared in a `Breakpoint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+}
+
+/// Stops the `Debugger` before a cycle runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// before the cycle with the number
+    Cycle(usize),
+    /// as soon as the register compares true with the value
+    Register(Register, Comparison, i32),
+}
+
+impl Breakpoint {
+    /// checks the breakpoint before the cycle `next_cycle` runs with the registers
+    fn hits(&self, next_cycle: usize, registers: &Registers) -> bool {
+        match *self {
+            Breakpoint::Cycle(cycle) => cycle == next_cycle,
+            Breakpoint::Register(register, comparison, value) => {
+                let current = registers.get(register);
+                match comparison {
+                    Comparison::Equal => current == value,
+                    Comparison::NotEqual => current != value,
+                    Comparison::Less => current < value,
+                    Comparison::Greater => current > value,
+                }
             }
         }
     }
 }
 
-pub fn solve_star_two(input: &str) -> Vec<String> {
-    let instructions = parse_input(input);
-    let mut cpu = CPU::new();
-    let max = instructions.iter().map(|(i, _)| i).max().unwrap();
-    let mut crt: Vec<String> = Vec::new();
-
-    for cycle in 1..max + 1 {
-        // get the current position
-        let position = ((cycle - 1) % CRT_DIMENSIONS.0) as i32;
-        if position == 0 {
-            // create a new line if we the position is 0
-            crt.push(String::new());
+/// Why the `Debugger` stopped running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// the breakpoint with the index hit before the cycle
+    Breakpoint { index: usize, cycle: usize },
+    /// the program has ended
+    Halted,
+}
+
+/// Runs the CPU step by step and records a trace of every cycle
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::{parse_program, Breakpoint, Debugger, Stop, EXAMPLE};
+///
+/// let mut debugger = Debugger::new(parse_program(EXAMPLE).unwrap());
+/// debugger.add_breakpoint(Breakpoint::Cycle(20));
+/// assert_eq!(debugger.run(), Stop::Breakpoint { index: 0, cycle: 20 });
+/// assert_eq!(debugger.cpu().registers().x(), 21);
+/// assert_eq!(debugger.run(), Stop::Halted);
+/// assert_eq!(debugger.trace().len(), 240);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    breakpoints: Vec<Breakpoint>,
+    trace: Vec<TraceEntry>,
+    /// the breakpoint index and cycle of the last stop
+    stopped_at: Option<(usize, usize)>,
+}
+
+impl Debugger {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            cpu: Cpu::new(program),
+            crt: Crt::default(),
+            breakpoints: Vec::new(),
+            trace: Vec::new(),
+            stopped_at: None,
+        }
+    }
+
+    /// Adds a breakpoint and returns its index
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+        self.stopped_at = None;
+    }
+
+    /// Runs a single cycle without looking at the breakpoints
+    pub fn step(&mut self) -> Option<&TraceEntry> {
+        let tick = self.cpu.tick()?;
+        let (pixel, lit) = self.crt.draw(tick.during.x());
+        self.trace.push(TraceEntry { tick, pixel, lit });
+        self.trace.last()
+    }
+
+    /// Runs until a breakpoint hits or the program ends.
+    /// Calling `run` again after a stop goes on with the breakpoints after the one
+    /// that stopped, so every breakpoint stops at most once before a cycle.
+    pub fn run(&mut self) -> Stop {
+        while !self.cpu.is_halted() {
+            let next_cycle = self.cpu.cycle() + 1;
+            let skip = match self.stopped_at {
+                Some((index, cycle)) if cycle == next_cycle => index + 1,
+                _ => 0,
+            };
+            let hit = self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .find(|(_, b)| b.hits(next_cycle, self.cpu.registers()));
+            if let Some((index, _)) = hit {
+                self.stopped_at = Some((index, next_cycle));
+                return Stop::Breakpoint {
+                    index,
+                    cycle: next_cycle,
+                };
+            }
+            self.step();
         }
-        // get the current line
-        let line = crt.last_mut().unwrap();
+        Stop::Halted
+    }
 
-        // draw the sprite
-        line.push(draw_sprite(position, &cpu));
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
 
-        // handle register
-        let instruction = instructions.get(&cycle);
-        cpu.compute(instruction);
+    /// The screen as drawn so far
+    pub fn crt(&self) -> &Crt {
+        &self.crt
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
     }
-    crt
 }
 
-fn draw_sprite(position: i32, cpu: &CPU) -> char {
-    if (position - cpu.register).abs() < 2 {
-        '#'
-    } else {
-        '.'
+/// Writes a trace as readable text, one line per cycle with the `x` register
+/// and every other register that changed
+pub fn trace_to_text(trace: &[TraceEntry]) -> String {
+    let mut out = String::new();
+    for entry in trace {
+        let tick = &entry.tick;
+        let mut registers = format!("x: {} -> {}", tick.during.x(), tick.after.x());
+        for index in 0..26u8 {
+            let register = Register(index);
+            if register != Register::X && tick.during.get(register) != tick.after.get(register) {
+                registers.push_str(&format!(
+                    ", {}: {} -> {}",
+                    register.name(),
+                    tick.during.get(register),
+                    tick.after.get(register)
+                ));
+            }
+        }
+        out.push_str(&format!(
+            "cycle {:>4} | {:<10} | {registers} | pixel ({:>2}, {}) {}\n",
+            tick.cycle,
+            tick.instruction.to_string(),
+            entry.pixel.0,
+            entry.pixel.1,
+            if entry.lit { '#' } else { '.' }
+        ));
     }
+    out
 }
 
-pub fn solve_star_one(input: &str) -> i32 {
-    let instructions = parse_input(input);
-    let mut cpu = CPU::new();
+/// Writes a trace as CSV with a header.
+/// Every register from `a` to `z` gets a column for its value during and after the cycle.
+pub fn trace_to_csv(trace: &[TraceEntry]) -> String {
+    let registers: Vec<Register> = (0..26).map(Register).collect();
+    let mut out = String::from("cycle,instruction,completed");
+    for register in &registers {
+        let name = register.name();
+        out.push_str(&format!(",{name}_during,{name}_after"));
+    }
+    out.push_str(",pixel_x,pixel_y,lit\n");
+
+    for entry in trace {
+        let tick = &entry.tick;
+        out.push_str(&format!(
+            "{},{},{}",
+            tick.cycle, tick.instruction, tick.completed
+        ));
+        for &register in &registers {
+            out.push_str(&format!(
+                ",{},{}",
+                tick.during.get(register),
+                tick.after.get(register)
+            ));
+        }
+        out.push_str(&format!(
+            ",{},{},{}\n",
+            entry.pixel.0, entry.pixel.1, entry.lit
+        ));
+    }
+    out
+}
+
+/// Compares two runs and returns the first cycle where they differ.
+/// A run that ends earlier differs in the first cycle it is missing.
+pub fn first_difference(a: &[TraceEntry], b: &[TraceEntry]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| a != b)
+        .or((a.len() != b.len()).then_some(a.len().min(b.len())))
+        .map(|index| index + 1)
+}
+
+/// A program built by `assemble`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assembly {
+    pub program: Vec<Instruction>,
+    /// the labels in source order, with the cycle the instruction after them starts in
+    pub labels: Vec<(String, usize)>,
+}
+
+impl Assembly {
+    /// the cycle of the label
+    pub fn label(&self, name: &str) -> Option<usize> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|&(_, cycle)| cycle)
+    }
+}
+
+/// Why `assemble` failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsmError {
+    /// the 1-based line number, for errors inside a macro the line of the call
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// The deepest macro calls can be nested, to stop recursive macros
+const MAX_MACRO_DEPTH: usize = 32;
+
+/// The most instructions an assembled program can have, so a `.wait` with a huge count
+/// or macros that double their body on every level fail instead of running out of memory
+pub const MAX_PROGRAM_LENGTH: usize = 1 << 20;
+
+struct Macro<'a> {
+    name: &'a str,
+    params: Vec<&'a str>,
+    body: Vec<&'a str>,
+}
 
-    let mut milestones: HashMap<i32, i32> =
-        HashMap::from([(20, 0), (60, 0), (100, 0), (140, 0), (180, 0), (220, 0)]);
+/// Assembles a program for the CPU. Besides the instructions it supports
+/// - comments starting with `;`
+/// - labels like `start:`, alone or in front of a statement
+/// - `.wait N` for `N` times `noop`
+/// - `.at N` for as many `noop` as needed to start the next instruction in cycle `N`
+/// - macros with parameters between `.macro name a b` and `.endm`, called like `name 3 4`
+///
+/// Programs with more than `MAX_PROGRAM_LENGTH` instructions are rejected.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::{assemble, Instruction, Register};
+///
+/// let source = "\
+/// .macro jump to      ; sets x in two cycles
+///     addx to
+/// .endm
+/// start: jump 5
+/// .at 5
+/// end: noop";
+/// let assembly = assemble(source).unwrap();
+/// assert_eq!(assembly.program[0], Instruction::Add(Register::X, 5));
+/// assert_eq!(assembly.program.len(), 4);
+/// assert_eq!(assembly.label("end"), Some(5));
+/// ```
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    let mut macros: Vec<Macro> = Vec::new();
+    let mut statements = Vec::new();
+    let mut open: Option<(usize, Macro)> = None;
 
-    for cycle in 1..221 {
-        if milestones.contains_key(&cycle) {
-            milestones.insert(cycle, cpu.register);
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |reason| AsmError {
+            line: line_number,
+            reason,
+        };
+        let line = line.split(';').next().unwrap_or_default().trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some(".macro") => {
+                if open.is_some() {
+                    return Err(error("Nested macro definition"));
+                }
+                let name = words.next().ok_or(error("Macro without a name"))?;
+                if name.starts_with('.') || Instruction::from_str(&format!("{name} 0")).is_ok() {
+                    return Err(error("Invalid macro name"));
+                }
+                if name == "noop" || macros.iter().any(|m| m.name == name) {
+                    return Err(error("Duplicate macro"));
+                }
+                let macro_definition = Macro {
+                    name,
+                    params: words.collect(),
+                    body: Vec::new(),
+                };
+                open = Some((line_number, macro_definition));
+            }
+            Some(".endm") => match open.take() {
+                Some((_, macro_definition)) => macros.push(macro_definition),
+                None => return Err(error("`.endm` without `.macro`")),
+            },
+            _ => match open.as_mut() {
+                Some((_, macro_definition)) => macro_definition.body.push(line),
+                None => statements.push((line_number, line)),
+            },
         }
+    }
+    if let Some((line, _)) = open {
+        return Err(AsmError {
+            line,
+            reason: "Macro without `.endm`",
+        });
+    }
 
-        // handle register
-        let instruction = instructions.get(&(cycle as usize));
-        cpu.compute(instruction);
+    let mut assembly = Assembly {
+        program: Vec::new(),
+        labels: Vec::new(),
+    };
+    for (line, statement) in statements {
+        assemble_statement(statement, &macros, 0, &mut assembly)
+            .map_err(|reason| AsmError { line, reason })?;
     }
+    Ok(assembly)
+}
 
-    milestones
-        .iter()
-        .map(|(&key, &value)| (key as i32) * value)
-        .sum()
+fn assemble_statement(
+    statement: &str,
+    macros: &[Macro],
+    depth: usize,
+    assembly: &mut Assembly,
+) -> Result<(), &'static str> {
+    let next_cycle = |assembly: &Assembly| {
+        1 + assembly
+            .program
+            .iter()
+            .map(|i| i.cycles() as usize)
+            .sum::<usize>()
+    };
+
+    let mut statement = statement.trim();
+    if let Some((label, rest)) = statement.split_once(':') {
+        if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err("Invalid label");
+        }
+        if assembly.label(label).is_some() {
+            return Err("Duplicate label");
+        }
+        assembly
+            .labels
+            .push((label.to_string(), next_cycle(assembly)));
+        statement = rest.trim();
+    }
+
+    let words: Vec<&str> = statement.split_whitespace().collect();
+    match words[..] {
+        [] => (),
+        [".wait", count] => {
+            let count: usize = count.parse().map_err(|_| "Invalid number")?;
+            push_noops(assembly, count)?;
+        }
+        [".at", cycle] => {
+            let cycle: usize = cycle.parse().map_err(|_| "Invalid number")?;
+            let current = next_cycle(assembly);
+            if cycle < current {
+                return Err("The cycle of `.at` has already passed");
+            }
+            push_noops(assembly, cycle - current)?;
+        }
+        [directive, ..] if directive.starts_with('.') => return Err("Invalid directive"),
+        [name, ref args @ ..] if macros.iter().any(|m| m.name == name) => {
+            let macro_definition = macros.iter().find(|m| m.name == name).unwrap();
+            if depth >= MAX_MACRO_DEPTH {
+                return Err("Macros are nested too deep");
+            }
+            if args.len() != macro_definition.params.len() {
+                return Err("Wrong number of macro arguments");
+            }
+            for line in &macro_definition.body {
+                let expanded: Vec<&str> = line
+                    .split_whitespace()
+                    .map(|word| {
+                        macro_definition
+                            .params
+                            .iter()
+                            .position(|param| *param == word)
+                            .map_or(word, |index| args[index])
+                    })
+                    .collect();
+                assemble_statement(&expanded.join(" "), macros, depth + 1, assembly)?;
+            }
+        }
+        _ => {
+            let instruction = Instruction::from_str(statement)?;
+            if assembly.program.len() >= MAX_PROGRAM_LENGTH {
+                return Err("The program is too long");
+            }
+            assembly.program.push(instruction);
+        }
+    }
+    Ok(())
 }
 
-fn parse_input(input: &str) -> HashMap<usize, Instruction> {
-    let mut cycle = 0;
-    let mut instruction_map: HashMap<usize, Instruction> = HashMap::new();
-    for line in input.lines() {
-        let instruction = Instruction::from_str(line).unwrap();
-        match instruction {
-            Instruction::Add(_) => cycle += 2,
-            Instruction::Noop => cycle += 1,
+/// Adds `count` times `noop`, if the program doesn't get too long
+fn push_noops(assembly: &mut Assembly, count: usize) -> Result<(), &'static str> {
+    if count > MAX_PROGRAM_LENGTH - assembly.program.len() {
+        return Err("The program is too long");
+    }
+    assembly
+        .program
+        .extend(std::iter::repeat_n(Instruction::Noop, count));
+    Ok(())
+}
+
+/// Writes a program as assembly, with the cycle every line starts in and the `x`
+/// register after it. Runs of `noop` become `.wait`, so `assemble` reads it back.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let mut out = String::new();
+    let mut registers = Registers::default();
+    let mut cycle = 1;
+    let mut index = 0;
+    while index < program.len() {
+        let noops = program[index..]
+            .iter()
+            .take_while(|&&instruction| instruction == Instruction::Noop)
+            .count();
+        let (text, count) = match noops {
+            0 | 1 => (program[index].to_string(), 1),
+            _ => (format!(".wait {noops}"), noops),
         };
-        instruction_map.insert(cycle, instruction);
+        let start = cycle;
+        for instruction in &program[index..index + count] {
+            instruction.execute(&mut registers);
+            cycle += instruction.cycles() as usize;
+        }
+        index += count;
+        out.push_str(&format!(
+            "{text:<12}; cycle {start:>3}, x = {}\n",
+            registers.x()
+        ));
     }
-    instruction_map
+    out
 }
 
-#[cfg(test)]
-mod test_day_10 {
-    use super::*;
-    use crate::load_input;
+/// Why `compile_bitmap` failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompileError {
+    /// the bitmap doesn't have the size of the CRT
+    Size { width: usize, height: usize },
+    /// a character other than `#` and `.`
+    InvalidPixel { x: usize, y: usize },
+    /// no program can draw the pixel together with the pixels before it
+    Unreachable { x: usize, y: usize },
+}
 
-    #[test]
-    fn test_example_star_one() {
-        let r = solve_star_one(EXAMPLE);
-        assert_eq!(r, 13140);
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Size { width, height } => {
+                write!(f, "the bitmap is {width}x{height}, not the size of the CRT")
+            }
+            CompileError::InvalidPixel { x, y } => write!(f, "invalid pixel at ({x}, {y})"),
+            CompileError::Unreachable { x, y } => write!(f, "the pixel ({x}, {y}) can't be drawn"),
+        }
     }
+}
 
-    #[test]
-    fn test_star_one() {
-        let input = load_input(10);
-        let r = solve_star_one(&input);
-        assert_eq!(r, 14760);
+impl std::error::Error for CompileError {}
+
+/// Compiles a bitmap of `#` and `.` rows into a program that makes the default
+/// CRT draw it, with as few instructions as possible.
+///
+/// The `x` register only changes after an `addx`, which keeps it for two cycles,
+/// so not every bitmap can be drawn.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::{compile_bitmap, Cpu, Crt};
+///
+/// let bitmap: Vec<String> = (0..6).map(|y| ".".repeat(y * 7) + &"#".repeat(40 - y * 7)).collect();
+/// let mut crt = Crt::default();
+/// crt.run(Cpu::new(compile_bitmap(&bitmap).unwrap()));
+/// assert_eq!(crt.rows(), bitmap);
+/// ```
+pub fn compile_bitmap(bitmap: &[String]) -> Result<Vec<Instruction>, CompileError> {
+    let crt = Crt::default();
+    let (width, height) = (crt.width(), crt.height());
+    let bitmap_width = bitmap.first().map_or(0, |row| row.len());
+    if bitmap.len() != height || bitmap.iter().any(|row| row.len() != width) {
+        return Err(CompileError::Size {
+            width: bitmap_width,
+            height: bitmap.len(),
+        });
+    }
+    let mut pixels = Vec::new();
+    for (y, row) in bitmap.iter().enumerate() {
+        for (x, pixel) in row.chars().enumerate() {
+            match pixel {
+                '#' => pixels.push(true),
+                '.' => pixels.push(false),
+                _ => return Err(CompileError::InvalidPixel { x, y }),
+            }
+        }
     }
 
-    #[test]
-    fn test_example_star_two() {
-        let expected = "##..##..##..##..##..##..##..##..##..##..";
-        let result = solve_star_two(EXAMPLE);
-        assert_eq!(expected, result[0]);
+    // every sprite left or right of all columns looks the same, so a few values
+    // of `x` around the screen are enough
+    let low = -(width as i32);
+    let high = 2 * width as i32;
+    let values = (high - low + 1) as usize;
+    let x_at = |state: usize| low + state as i32;
+    let draws =
+        |cycle: usize, state: usize| crt.sprite_covers(cycle % width, x_at(state)) == pixels[cycle];
+
+    // best[cycle][state] is the fewest instructions that drew the first `cycle`
+    // pixels and end with `x` at the state, with the previous step to get there
+    let mut best: Vec<Vec<Option<(usize, usize, Instruction)>>> =
+        vec![vec![None; values]; pixels.len() + 1];
+    let mut count: Vec<Vec<usize>> = vec![vec![usize::MAX; values]; pixels.len() + 1];
+    let start = (1 - low) as usize;
+    count[0][start] = 0;
+    let mut furthest = 0;
+
+    for cycle in 0..pixels.len() {
+        for state in 0..values {
+            let instructions = count[cycle][state];
+            if instructions == usize::MAX || !draws(cycle, state) {
+                continue;
+            }
+            furthest = furthest.max(cycle + 1);
+            if instructions + 1 < count[cycle + 1][state] {
+                count[cycle + 1][state] = instructions + 1;
+                best[cycle + 1][state] = Some((cycle, state, Instruction::Noop));
+            }
+            if cycle + 2 > pixels.len() || !draws(cycle + 1, state) {
+                continue;
+            }
+            furthest = furthest.max(cycle + 2);
+            for next in 0..values {
+                if instructions + 1 < count[cycle + 2][next] {
+                    count[cycle + 2][next] = instructions + 1;
+                    let value = x_at(next) - x_at(state);
+                    best[cycle + 2][next] =
+                        Some((cycle, state, Instruction::Add(Register::X, value)));
+                }
+            }
+        }
     }
 
-    #[test]
-    fn test_star_two() {
-        let expected = "\
-####.####..##..####.###..#..#.###..####.
-#....#....#..#.#....#..#.#..#.#..#.#....
-###..###..#....###..#..#.#..#.#..#.###..
-#....#....#.##.#....###..#..#.###..#....
-#....#....#..#.#....#.#..#..#.#.#..#....
-####.#.....###.####.#..#..##..#..#.####.";
-        let input = load_input(10);
-        let result = solve_star_two(&input);
-        assert_eq!(expected, result.join("\n"));
+    let end = (0..values)
+        .filter(|&state| count[pixels.len()][state] != usize::MAX)
+        .min_by_key(|&state| count[pixels.len()][state]);
+    let Some(mut state) = end else {
+        return Err(CompileError::Unreachable {
+            x: furthest % width,
+            y: furthest / width,
+        });
+    };
+    let mut program = Vec::new();
+    let mut cycle = pixels.len();
+    while let Some((previous_cycle, previous_state, instruction)) = best[cycle][state] {
+        program.push(instruction);
+        cycle = previous_cycle;
+        state = previous_state;
+    }
+    program.reverse();
+    Ok(program)
+}
+
+/// The letters of the 4×6 font the CRT draws, row by row
+const FONT: [(char, [&str; 6]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// Every glyph is 4 pixels wide followed by one blank column
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_SPACING: usize = 5;
+
+/// The glyphs `read_letters` could not recognize
+#[derive(Debug, PartialEq, Eq)]
+pub struct OcrError {
+    /// the 0-based positions of the unrecognized glyphs
+    pub positions: Vec<usize>,
+    /// the letters that were read, with `?` for every unrecognized glyph
+    pub partial: String,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let positions: Vec<String> = self.positions.iter().map(|p| p.to_string()).collect();
+        write!(
+            f,
+            "unrecognized glyphs at positions {} (read \"{}\")",
+            positions.join(", "),
+            self.partial
+        )
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Reads the capital letters the CRT shows
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_10::read_letters;
+///
+/// let crt: Vec<String> = ["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."]
+///     .map(String::from)
+///     .to_vec();
+/// assert_eq!(read_letters(&crt), Ok("H".to_string()));
+/// ```
+pub fn read_letters(crt: &[String]) -> Result<String, OcrError> {
+    let width = crt.iter().map(|row| row.len()).max().unwrap_or_default();
+    let count = width.div_ceil(GLYPH_SPACING);
+
+    let mut partial = String::new();
+    let mut positions = Vec::new();
+    for position in 0..count {
+        let start = position * GLYPH_SPACING;
+        let cell: Vec<Option<&str>> = crt
+            .iter()
+            .map(|row| row.get(start..start + GLYPH_WIDTH))
+            .collect();
+        let letter = FONT
+            .iter()
+            .find(|(_, glyph)| {
+                cell.len() == glyph.len()
+                    && cell
+                        .iter()
+                        .zip(glyph)
+                        .all(|(row, glyph_row)| *row == Some(*glyph_row))
+            })
+            .map(|(letter, _)| *letter);
+        match letter {
+            Some(letter) => partial.push(letter),
+            None => {
+                partial.push('?');
+                positions.push(position);
+            }
+        }
+    }
+
+    if positions.is_empty() {
+        Ok(partial)
+    } else {
+        Err(OcrError { positions, partial })
     }
 }
 
+pub fn solve_star_two(input: &str) -> Vec<String> {
+    let mut crt = Crt::default();
+    crt.run(Cpu::new(
+        parse_program(input).expect("The program is invalid"),
+    ));
+    crt.rows()
+}
+
+pub fn solve_star_one(input: &str) -> i32 {
+    let mut cpu = Cpu::new(parse_program(input).expect("The program is invalid"));
+    let milestones = [20, 60, 100, 140, 180, 220];
+
+    std::iter::from_fn(|| cpu.tick())
+        .filter(|tick| milestones.contains(&tick.cycle))
+        .map(|tick| tick.cycle as i32 * tick.during.x())
+        .sum()
+}
+
 pub const EXAMPLE: &str = "\
 addx 15
 addx -11
@@ -302,3 +1195,448 @@ addx -11
 noop
 noop
 noop";
+
+#[cfg(test)]
+mod test_day_10 {
+    use super::*;
+    use crate::load_input;
+
+    #[test]
+    fn test_example_star_one() {
+        let r = solve_star_one(EXAMPLE);
+        assert_eq!(r, 13140);
+    }
+
+    #[test]
+    fn test_star_one() {
+        let input = load_input(10);
+        let r = solve_star_one(&input);
+        assert_eq!(r, 14760);
+    }
+
+    #[test]
+    fn test_example_star_two() {
+        let expected = "##..##..##..##..##..##..##..##..##..##..";
+        let result = solve_star_two(EXAMPLE);
+        assert_eq!(expected, result[0]);
+    }
+
+    #[test]
+    fn test_star_two() {
+        let expected = "\
+####.####..##..####.###..#..#.###..####.
+#....#....#..#.#....#..#.#..#.#..#.#....
+###..###..#....###..#..#.#..#.#..#.###..
+#....#....#.##.#....###..#..#.###..#....
+#....#....#..#.#....#.#..#..#.#.#..#....
+####.#.....###.####.#..#..##..#..#.####.";
+        let input = load_input(10);
+        let result = solve_star_two(&input);
+        assert_eq!(expected, result.join("\n"));
+    }
+
+    #[test]
+    fn test_instruction_from_str() {
+        assert_eq!(
+            Instruction::from_str("addx -7"),
+            Ok(Instruction::Add(Register::X, -7))
+        );
+        assert_eq!(
+            Instruction::from_str("addy 2"),
+            Ok(Instruction::Add(Register::from_name('y').unwrap(), 2))
+        );
+        assert_eq!(Instruction::from_str("noop"), Ok(Instruction::Noop));
+        assert_eq!(Instruction::from_str("addx x"), Err("Invalid number"));
+        assert_eq!(Instruction::from_str("addX 1"), Err("Invalid register"));
+        assert_eq!(Instruction::from_str("jmp 1"), Err("Invalid format"));
+        assert_eq!(Instruction::Add(Register::X, -7).to_string(), "addx -7");
+    }
+
+    #[test]
+    fn test_cpu_ticks() {
+        let program = parse_program("noop\naddx 3\naddy -5\naddx 2").unwrap();
+        let mut cpu = Cpu::new(program);
+        let ticks: Vec<Tick> = std::iter::from_fn(|| cpu.tick()).collect();
+
+        assert_eq!(ticks.len(), 7);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.cycle(), 7);
+        assert!(ticks[0].completed);
+        assert!(!ticks[1].completed);
+        assert_eq!(ticks[2].during.x(), 1);
+        assert_eq!(ticks[2].after.x(), 4);
+
+        let y = Register::from_name('y').unwrap();
+        assert_eq!(ticks[4].after.get(y), -5);
+        assert_eq!(cpu.registers().x(), 6);
+        assert_eq!(cpu.registers().get(y), -5);
+        assert_eq!(cpu.tick(), None);
+    }
+
+    #[test]
+    fn test_register_breakpoint() {
+        let mut debugger = Debugger::new(parse_program(EXAMPLE).unwrap());
+        debugger.add_breakpoint(Breakpoint::Register(Register::X, Comparison::Equal, 21));
+        let stop = debugger.run();
+        assert_eq!(
+            stop,
+            Stop::Breakpoint {
+                index: 0,
+                cycle: 19
+            }
+        );
+        assert_eq!(debugger.trace().len(), 18);
+
+        // running again goes on, but stops again as long as the condition holds
+        assert_eq!(
+            debugger.run(),
+            Stop::Breakpoint {
+                index: 0,
+                cycle: 20
+            }
+        );
+        assert_eq!(debugger.trace().len(), 19);
+        assert_eq!(debugger.step().unwrap().tick.cycle, 20);
+        let stop = debugger.run();
+        assert_eq!(
+            stop,
+            Stop::Breakpoint {
+                index: 0,
+                cycle: 21
+            }
+        );
+
+        debugger.clear_breakpoints();
+        debugger.add_breakpoint(Breakpoint::Register(Register::X, Comparison::Less, 0));
+        let stop = debugger.run();
+        assert_eq!(
+            stop,
+            Stop::Breakpoint {
+                index: 0,
+                cycle: 210
+            }
+        );
+        assert!(debugger.cpu().registers().x() < 0);
+
+        debugger.clear_breakpoints();
+        assert_eq!(debugger.run(), Stop::Halted);
+    }
+
+    #[test]
+    fn test_breakpoints_before_the_first_cycle() {
+        let mut debugger = Debugger::new(parse_program(EXAMPLE).unwrap());
+        debugger.add_breakpoint(Breakpoint::Cycle(1));
+        debugger.add_breakpoint(Breakpoint::Register(Register::X, Comparison::Equal, 1));
+        debugger.add_breakpoint(Breakpoint::Cycle(3));
+
+        // both hit before anything ran, each one stops once
+        assert_eq!(debugger.run(), Stop::Breakpoint { index: 0, cycle: 1 });
+        assert_eq!(debugger.run(), Stop::Breakpoint { index: 1, cycle: 1 });
+        assert!(debugger.trace().is_empty());
+        assert_eq!(debugger.run(), Stop::Breakpoint { index: 1, cycle: 2 });
+        assert_eq!(debugger.run(), Stop::Breakpoint { index: 2, cycle: 3 });
+        assert_eq!(debugger.trace().len(), 2);
+    }
+
+    #[test]
+    fn test_trace_exports() {
+        let mut debugger = Debugger::new(parse_program("noop\naddx 3\naddy -5").unwrap());
+        debugger.run();
+        let trace = debugger.trace();
+
+        let text = trace_to_text(trace);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "cycle    1 | noop       | x: 1 -> 1 | pixel ( 0, 0) #"
+        );
+        assert_eq!(
+            lines[2],
+            "cycle    3 | addx 3     | x: 1 -> 4 | pixel ( 2, 0) #"
+        );
+        assert_eq!(
+            lines[4],
+            "cycle    5 | addy -5    | x: 4 -> 4, y: 0 -> -5 | pixel ( 4, 0) #"
+        );
+
+        let csv = trace_to_csv(trace);
+        let lines: Vec<&str> = csv.lines().collect();
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert_eq!(header.len(), 3 + 2 * 26 + 3);
+        assert_eq!(
+            header[..5],
+            ["cycle", "instruction", "completed", "a_during", "a_after"]
+        );
+        assert_eq!(
+            header[header.len() - 5..],
+            ["z_during", "z_after", "pixel_x", "pixel_y", "lit"]
+        );
+        assert_eq!(lines.len(), 6);
+
+        let column = |line: &str, name: &str| {
+            let index = header.iter().position(|&column| column == name).unwrap();
+            line.split(',').nth(index).unwrap().to_string()
+        };
+        assert!(lines[3].starts_with("3,addx 3,true,"));
+        assert!(lines[3].ends_with(",2,0,true"));
+        assert_eq!(column(lines[3], "x_during"), "1");
+        assert_eq!(column(lines[3], "x_after"), "4");
+        assert_eq!(column(lines[5], "y_during"), "0");
+        assert_eq!(column(lines[5], "y_after"), "-5");
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let run = |program: &str| {
+            let mut debugger = Debugger::new(parse_program(program).unwrap());
+            debugger.run();
+            debugger.trace().to_vec()
+        };
+        let a = run("noop\naddx 3\naddx 1");
+        assert_eq!(first_difference(&a, &a), None);
+        assert_eq!(first_difference(&a, &run("noop\naddx 2\naddx 1")), Some(2));
+        assert_eq!(first_difference(&a, &run("noop\naddx 3")), Some(4));
+    }
+
+    #[test]
+    fn test_read_letters() {
+        let input = load_input(10);
+        let crt = solve_star_two(&input);
+        assert_eq!(read_letters(&crt), Ok("EFGERURE".to_string()));
+    }
+
+    #[test]
+    fn test_read_letter_i() {
+        let crt: Vec<String> = [
+            "#..#..###..##.",
+            "#..#...#..#..#",
+            "####...#..#...",
+            "#..#...#..#...",
+            "#..#...#..#..#",
+            "#..#..###..##.",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(read_letters(&crt), Ok("HIC".to_string()));
+    }
+
+    #[test]
+    fn test_read_letters_unrecognized() {
+        let mut crt = solve_star_two(&load_input(10));
+        // break the second and the last letter
+        crt[0].replace_range(5..6, ".");
+        crt[5].replace_range(38..39, ".");
+        let error = read_letters(&crt).unwrap_err();
+        assert_eq!(error.positions, vec![1, 7]);
+        assert_eq!(error.partial, "E?GERUR?");
+        assert_eq!(
+            error.to_string(),
+            "unrecognized glyphs at positions 1, 7 (read \"E?GERUR?\")"
+        );
+    }
+
+    #[test]
+    fn test_read_letters_missing_rows() {
+        let crt = solve_star_two(&load_input(10));
+        let error = read_letters(&crt[..5]).unwrap_err();
+        assert_eq!(error.positions, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_crt_sprite_width() {
+        let crt = Crt::new(10, 1).with_sprite_width(4);
+        let covered: Vec<usize> = (0..10).filter(|&x| crt.sprite_covers(x, 5)).collect();
+        assert_eq!(covered, vec![4, 5, 6, 7]);
+        let crt = crt.with_sprite_width(5);
+        let covered: Vec<usize> = (0..10).filter(|&x| crt.sprite_covers(x, 0)).collect();
+        assert_eq!(covered, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_crt_wraps_around() {
+        let mut crt = Crt::new(2, 2).with_sprite_width(1);
+        let pixels: Vec<_> = [0, 0, 1, 0, 1].iter().map(|&x| crt.draw(x)).collect();
+        assert_eq!(
+            pixels,
+            vec![
+                ((0, 0), true),
+                ((1, 0), false),
+                ((0, 1), false),
+                ((1, 1), false),
+                ((0, 0), false),
+            ]
+        );
+        assert_eq!(crt.rows(), vec!["..", ".."]);
+    }
+
+    #[test]
+    fn test_crt_export() {
+        let mut crt = Crt::new(3, 3).with_sprite_width(1);
+        for x in [0, 9, 9, 9, 1, 9, 9, 9, 2] {
+            crt.draw(x);
+        }
+        assert_eq!(crt.render(), "#..\n.#.\n..#");
+        assert_eq!(crt.to_pbm(), "P1\n3 3\n1 0 0\n0 1 0\n0 0 1\n");
+        assert_eq!(crt.to_blocks(), "▀▄ \n  ▀");
+    }
+
+    #[test]
+    fn test_debugger_crt() {
+        let input = load_input(10);
+        let mut debugger = Debugger::new(parse_program(&input).unwrap());
+        debugger.run();
+        assert_eq!(debugger.crt().rows(), solve_star_two(&input));
+    }
+
+    /// renders the letters in the font of the CRT, filled up with dark columns
+    fn text_bitmap(text: &str) -> Vec<String> {
+        (0..6)
+            .map(|row| {
+                let line: String = text
+                    .chars()
+                    .map(|letter| {
+                        let (_, glyph) = FONT.iter().find(|(l, _)| *l == letter).unwrap();
+                        format!("{}.", glyph[row])
+                    })
+                    .collect();
+                format!("{line:.<40}")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = "\
+; draws a pattern
+.macro pair a b
+    addx a
+    addx b
+.endm
+.macro twice a
+    pair a a  ; macros can call macros
+.endm
+start:
+    twice 2
+    .wait 2
+middle: noop
+    .at 10
+end:
+    addx -3";
+        let assembly = assemble(source).unwrap();
+        let program =
+            parse_program("addx 2\naddx 2\nnoop\nnoop\nnoop\nnoop\nnoop\naddx -3").unwrap();
+        assert_eq!(assembly.program, program);
+        assert_eq!(
+            assembly.labels,
+            vec![
+                ("start".to_string(), 1),
+                ("middle".to_string(), 7),
+                ("end".to_string(), 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = |source: &str| assemble(source).unwrap_err();
+        assert_eq!(
+            error("noop\naddx three"),
+            AsmError {
+                line: 2,
+                reason: "Invalid number"
+            }
+        );
+        assert_eq!(error("a: noop\na: noop").reason, "Duplicate label");
+        assert_eq!(error("noop\n.macro m\nnoop").line, 2);
+        assert_eq!(error(".endm").reason, "`.endm` without `.macro`");
+        assert_eq!(
+            error(".macro m a\n.endm\nm").reason,
+            "Wrong number of macro arguments"
+        );
+        assert_eq!(error(".macro m\nm\n.endm\n\nm").line, 5);
+        assert_eq!(
+            error(".macro m\nm\n.endm\nm").reason,
+            "Macros are nested too deep"
+        );
+        assert_eq!(
+            error("addx 5\n.at 2").reason,
+            "The cycle of `.at` has already passed"
+        );
+        assert_eq!(error(".jump 3").reason, "Invalid directive");
+        assert_eq!(error(".macro addx\n.endm").reason, "Invalid macro name");
+
+        // the program can't get arbitrarily long
+        assert_eq!(
+            error("noop\n.wait 1000000000000"),
+            AsmError {
+                line: 2,
+                reason: "The program is too long"
+            }
+        );
+        assert_eq!(error(".at 1000000000000").reason, "The program is too long");
+        assert_eq!(error(".wait 99999999999999999999").reason, "Invalid number");
+        let source = format!(".wait {MAX_PROGRAM_LENGTH}");
+        assert_eq!(assemble(&source).unwrap().program.len(), MAX_PROGRAM_LENGTH);
+        assert_eq!(error(&format!("{source}\nnoop")).line, 2);
+        // every macro calls the one before twice, so `m5` has 2^21 instructions
+        let mut source = String::from(".macro m0\n.wait 65536\n.endm\n");
+        for level in 1..=5 {
+            let previous = level - 1;
+            source += &format!(".macro m{level}\nm{previous}\nm{previous}\n.endm\n");
+        }
+        source += "m5";
+        assert_eq!(error(&source).reason, "The program is too long");
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = parse_program("addx 15\nnoop\nnoop\nnoop\naddx -11\nnoop").unwrap();
+        let expected = "\
+addx 15     ; cycle   1, x = 16
+.wait 3     ; cycle   3, x = 16
+addx -11    ; cycle   6, x = 5
+noop        ; cycle   8, x = 5
+";
+        assert_eq!(disassemble(&program), expected);
+
+        let program = parse_program(&load_input(10)).unwrap();
+        assert_eq!(assemble(&disassemble(&program)).unwrap().program, program);
+    }
+
+    #[test]
+    fn test_compile_bitmap() {
+        let expected = solve_star_two(&load_input(10));
+        let program = compile_bitmap(&expected).unwrap();
+        let mut crt = Crt::default();
+        crt.run(Cpu::new(program));
+        assert_eq!(crt.rows(), expected);
+
+        let bitmap = text_bitmap("ZEBRAHUG");
+        let mut crt = Crt::default();
+        crt.run(Cpu::new(compile_bitmap(&bitmap).unwrap()));
+        assert_eq!(read_letters(&crt.rows()), Ok("ZEBRAHUG".to_string()));
+    }
+
+    #[test]
+    fn test_compile_bitmap_errors() {
+        let mut bitmap = vec![".".repeat(40); 6];
+        bitmap[0].replace_range(0..2, "#.");
+        assert_eq!(
+            compile_bitmap(&bitmap),
+            Err(CompileError::Unreachable { x: 1, y: 0 })
+        );
+        bitmap[0].replace_range(0..2, "#x");
+        assert_eq!(
+            compile_bitmap(&bitmap),
+            Err(CompileError::InvalidPixel { x: 1, y: 0 })
+        );
+        assert_eq!(
+            compile_bitmap(&bitmap[..5]),
+            Err(CompileError::Size {
+                width: 40,
+                height: 5
+            })
+        );
+    }
+}
@@ -1,37 +1,452 @@
 #![doc = include_str!("descriptions/day_11.md")]
-#[derive(Debug, PartialEq, Eq)]
+
+use std::collections::HashMap;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+/// The operators of an `Operation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    /// `*`, `/` and `%` bind stronger than `+` and `-`
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 2,
+        }
+    }
+}
+
+/// Why an `Operation` can't calculate the new worry level
+const BELOW_ZERO: &str = "The worry level went below zero";
+const DIVISION_BY_ZERO: &str = "Division by zero";
+const TOO_LARGE: &str = "The worry level doesn't fit into 128 bits";
+
+/// The numbers an `Operation` can calculate with. Every calculation is checked
+/// and fails if it over- or underflows or divides by zero.
+trait Worry: Sized {
+    fn from_u64(value: u64) -> Self;
+    fn apply(&self, operator: Operator, other: &Self) -> Result<Self, &'static str>;
+}
+
+impl Worry for u128 {
+    fn from_u64(value: u64) -> Self {
+        value as u128
+    }
+
+    fn apply(&self, operator: Operator, other: &Self) -> Result<Self, &'static str> {
+        match operator {
+            Operator::Add => self.checked_add(*other).ok_or(TOO_LARGE),
+            Operator::Subtract => self.checked_sub(*other).ok_or(BELOW_ZERO),
+            Operator::Multiply => self.checked_mul(*other).ok_or(TOO_LARGE),
+            Operator::Divide => self.checked_div(*other).ok_or(DIVISION_BY_ZERO),
+            Operator::Remainder => self.checked_rem(*other).ok_or(DIVISION_BY_ZERO),
+        }
+    }
+}
+
+impl Worry for BigUint {
+    fn from_u64(value: u64) -> Self {
+        BigUint::from_u64(value)
+    }
+
+    fn apply(&self, operator: Operator, other: &Self) -> Result<Self, &'static str> {
+        match operator {
+            Operator::Add => Ok(self.add(other)),
+            Operator::Subtract => self.sub(other).ok_or(BELOW_ZERO),
+            Operator::Multiply => Ok(self.mul(other)),
+            Operator::Divide => self
+                .div_rem(other)
+                .map(|(quotient, _)| quotient)
+                .ok_or(DIVISION_BY_ZERO),
+            Operator::Remainder => self
+                .div_rem(other)
+                .map(|(_, remainder)| remainder)
+                .ok_or(DIVISION_BY_ZERO),
+        }
+    }
+}
+
+/// An unsigned integer of any size for worry levels that can't be reduced.
+/// The limbs are in base 2^32 with the lowest first and without zeros at the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint(Vec<u32>);
+
+impl BigUint {
+    pub fn from_u64(value: u64) -> Self {
+        let mut number = BigUint(vec![value as u32, (value >> 32) as u32]);
+        number.normalize();
+        number
+    }
+
+    /// the number if it fits into 64 bits
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.0[..] {
+            [] => Some(0),
+            [low] => Some(low as u64),
+            [low, high] => Some((high as u64) << 32 | low as u64),
+            _ => None,
+        }
+    }
+
+    fn normalize(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn bits(&self) -> usize {
+        self.0
+            .last()
+            .map_or(0, |last| self.0.len() * 32 - last.leading_zeros() as usize)
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.0[index / 32] >> (index % 32) & 1 == 1
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut limbs = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0u64;
+        for index in 0..self.0.len().max(other.0.len()) {
+            let sum = carry
+                + *self.0.get(index).unwrap_or(&0) as u64
+                + *other.0.get(index).unwrap_or(&0) as u64;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        let mut number = BigUint(limbs);
+        number.normalize();
+        number
+    }
+
+    fn sub(&self, other: &Self) -> Option<Self> {
+        if self < other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.0.len());
+        let mut borrow = 0i64;
+        for (index, &limb) in self.0.iter().enumerate() {
+            let mut difference = limb as i64 - *other.0.get(index).unwrap_or(&0) as i64 - borrow;
+            borrow = (difference < 0) as i64;
+            difference += borrow << 32;
+            limbs.push(difference as u32);
+        }
+        let mut number = BigUint(limbs);
+        number.normalize();
+        Some(number)
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        let mut limbs = vec![0u32; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.0.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.0.len()] = carry as u32;
+        }
+        let mut number = BigUint(limbs);
+        number.normalize();
+        number
+    }
+
+    /// the quotient and the remainder, `None` for a division by zero
+    pub fn div_rem(&self, other: &Self) -> Option<(Self, Self)> {
+        if other.is_zero() {
+            return None;
+        }
+        if let [divisor] = other.0[..] {
+            let (quotient, remainder) = self.div_rem_small(divisor);
+            return Some((quotient, BigUint::from_u64(remainder as u64)));
+        }
+
+        // binary long division
+        let mut quotient = vec![0u32; self.0.len()];
+        let mut remainder = BigUint(Vec::new());
+        for index in (0..self.bits()).rev() {
+            remainder = remainder.add(&remainder);
+            if self.bit(index) {
+                remainder = remainder.add(&BigUint(vec![1]));
+            }
+            if remainder >= *other {
+                remainder = remainder.sub(other).unwrap();
+                quotient[index / 32] |= 1 << (index % 32);
+            }
+        }
+        let mut quotient = BigUint(quotient);
+        quotient.normalize();
+        Some((quotient, remainder))
+    }
+
+    fn div_rem_small(&self, divisor: u32) -> (Self, u32) {
+        let mut limbs = vec![0u32; self.0.len()];
+        let mut remainder = 0u64;
+        for (index, &limb) in self.0.iter().enumerate().rev() {
+            let current = remainder << 32 | limb as u64;
+            limbs[index] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        let mut quotient = BigUint(limbs);
+        quotient.normalize();
+        (quotient, remainder as u32)
+    }
+
+    fn rem_u64(&self, divisor: u64) -> u64 {
+        self.0.iter().rev().fold(0u128, |remainder, &limb| {
+            (remainder << 32 | limb as u128) % divisor as u128
+        }) as u64
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for BigUint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // split into blocks of nine decimal digits, the lowest first
+        let mut blocks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, block) = rest.div_rem_small(1_000_000_000);
+            blocks.push(block);
+            rest = quotient;
+        }
+        match blocks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{first}")?;
+                rest.iter()
+                    .rev()
+                    .try_for_each(|block| write!(f, "{block:09}"))
+            }
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The least common multiple of the tests, every worry level can be reduced by it
+/// without changing where the items are thrown. `None` if it doesn't fit into 64 bits.
+fn modulus(monkeys: &[Monkey]) -> Option<u64> {
+    monkeys.iter().try_fold(1, |modulus: u64, monkey| {
+        (modulus / gcd(modulus, monkey.test)).checked_mul(monkey.test)
+    })
+}
+
+/// The modulus the worry levels are reduced by, if that doesn't change the results.
+/// Only `+` and `*` and a policy that keeps the remainders allow it, otherwise the
+/// worry levels stay exact and an error is returned once they don't fit anymore.
+fn reduction(monkeys: &[Monkey], worry_behavior: &dyn WorryPolicy) -> Option<u64> {
+    if worry_behavior.keeps_remainders()
+        && monkeys
+            .iter()
+            .all(|monkey| monkey.operation.keeps_remainders())
+    {
+        modulus(monkeys)
+    } else {
+        None
+    }
+}
+
+/// How deep an operation can be nested, counting parentheses and chained operators.
+/// Deeper operations are rejected instead of overflowing the stack while parsing,
+/// evaluating or dropping them.
+const MAX_DEPTH: usize = 256;
+
+const TOO_DEEP: &str = "The operation is nested too deeply";
+
+/// The expression a monkey uses to change the worry level, like
+/// `new = (old + 3) * old`.
+///
+/// Only `+` and `*` keep the result right when the worry level is reduced
+/// modulo the least common multiple of the tests, see `keeps_remainders`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Operation {
-    Add(Option<u64>),
-    Multiply(Option<u64>),
+    /// the worry level before the inspection
+    Old,
+    Value(u64),
+    Binary(Operator, Box<Operation>, Box<Operation>),
 }
 
 impl Operation {
-    fn from_str(input: &str) -> Self {
-        let parts = input.split_whitespace().collect::<Vec<&str>>();
-        let value: Option<u64> = match parts.last() {
-            Some(&"old") => None,
-            Some(value) => Some(value.parse().unwrap()),
-            None => None,
-        };
+    /// calculates the new worry level, with the reason if the calculation fails
+    fn evaluate<W: Worry + Clone>(&self, old: &W) -> Result<W, &'static str> {
+        match self {
+            Operation::Old => Ok(old.clone()),
+            Operation::Value(value) => Ok(W::from_u64(*value)),
+            Operation::Binary(operator, left, right) => {
+                left.evaluate(old)?.apply(*operator, &right.evaluate(old)?)
+            }
+        }
+    }
+
+    /// `true` if the operation only adds and multiplies, then the remainder of the
+    /// result only depends on the remainder of `old` for any divisor
+    fn keeps_remainders(&self) -> bool {
+        match self {
+            Operation::Old | Operation::Value(_) => true,
+            Operation::Binary(operator, left, right) => {
+                matches!(operator, Operator::Add | Operator::Multiply)
+                    && left.keeps_remainders()
+                    && right.keeps_remainders()
+            }
+        }
+    }
 
-        match parts.iter().nth_back(1) {
-            Some(&"*") => Operation::Multiply(value),
-            Some(&"+") => Operation::Add(value),
-            Some(_) | None => panic!("The format isnt correct"),
+    /// parses the operands and operators from `tokens[*next..]` as long as the
+    /// operators bind at least as strong as `min_precedence`. `depth` is the number of
+    /// calls this one is nested in, the result comes with the height of its tree.
+    fn parse_expression(
+        tokens: &[Token],
+        next: &mut usize,
+        min_precedence: u8,
+        depth: usize,
+    ) -> Result<(Self, usize), &'static str> {
+        if depth >= MAX_DEPTH {
+            return Err(TOO_DEEP);
+        }
+        let (mut left, mut height) = Self::parse_operand(tokens, next, depth + 1)?;
+        while let Some(Token::Operator(operator)) = tokens.get(*next) {
+            if operator.precedence() < min_precedence {
+                break;
+            }
+            *next += 1;
+            let (right, right_height) =
+                Self::parse_expression(tokens, next, operator.precedence() + 1, depth + 1)?;
+            height = height.max(right_height) + 1;
+            if height > MAX_DEPTH {
+                return Err(TOO_DEEP);
+            }
+            left = Operation::Binary(*operator, Box::new(left), Box::new(right));
+        }
+        Ok((left, height))
+    }
+
+    fn parse_operand(
+        tokens: &[Token],
+        next: &mut usize,
+        depth: usize,
+    ) -> Result<(Self, usize), &'static str> {
+        let token = tokens.get(*next).ok_or("The operation ends too early")?;
+        *next += 1;
+        match token {
+            Token::Old => Ok((Operation::Old, 0)),
+            Token::Value(value) => Ok((Operation::Value(*value), 0)),
+            Token::Open => {
+                let inner = Self::parse_expression(tokens, next, 0, depth)?;
+                match tokens.get(*next) {
+                    Some(Token::Close) => {
+                        *next += 1;
+                        Ok(inner)
+                    }
+                    _ => Err("Missing closing parenthesis"),
+                }
+            }
+            Token::Close | Token::Operator(_) => Err("Expected `old`, a number or `(`"),
         }
     }
 }
 
-fn split_blocks(input: &str) -> Vec<&str> {
-    if input.contains("\r\n\r\n") {
-        input.split("\r\n\r\n").collect()
-    } else {
-        input.split("\n\n").collect()
+/// The parts of an `Operation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Old,
+    Value(u64),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '%' => Token::Operator(Operator::Remainder),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_alphanumeric() => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(index, c)) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = index + c.len_utf8();
+                    chars.next();
+                }
+                match &input[start..end] {
+                    "old" => Token::Old,
+                    word => Token::Value(word.parse().map_err(|_| "Invalid number")?),
+                }
+            }
+            _ => return Err("Invalid character in the operation"),
+        };
+        tokens.push(token);
     }
+    Ok(tokens)
 }
 
+impl FromStr for Operation {
+    type Err = &'static str;
+
+    /// Parses `new = <expression>` with `+ - * / %`, parentheses, numbers and `old`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expression = s
+            .trim()
+            .strip_prefix("new")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+            .ok_or("The operation has to start with `new =`")?;
+        let tokens = tokenize(expression)?;
+        let mut next = 0;
+        let (operation, _) = Operation::parse_expression(&tokens, &mut next, 0, 0)?;
+        if next < tokens.len() {
+            return Err("Unexpected token after the operation");
+        }
+        Ok(operation)
+    }
+}
+
+/// A monkey with the items it holds, parsed with `parse_monkeys`
 #[derive(Debug, PartialEq, Eq)]
-struct Monkey {
+pub struct Monkey {
     name: u8,
     items: Vec<u64>,
     operation: Operation,
@@ -40,53 +455,239 @@ struct Monkey {
     inspected_count: u32,
 }
 
+/// Why the monkeys could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonkeyError {
+    /// the position of the monkey in the input, starting with 0
+    pub monkey: usize,
+    /// the field like `Operation`, or `Monkey` for the header
+    pub field: String,
+    /// the 1-based line number
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "monkey {}, field `{}` (line {}): {}",
+            self.monkey, self.field, self.line, self.reason
+        )
+    }
+}
+
+impl std::error::Error for MonkeyError {}
+
+/// Why the monkeys can't be simulated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationError {
+    /// the input isn't valid
+    Monkey(MonkeyError),
+    /// the monkey with the number couldn't calculate a worry level in the 1-based round
+    Worry {
+        round: usize,
+        monkey: u8,
+        reason: &'static str,
+    },
+    /// the product of the highest inspection counts doesn't fit into 128 bits
+    ProductOverflow,
+}
+
+impl std::fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulationError::Monkey(error) => write!(f, "{error}"),
+            SimulationError::Worry {
+                round,
+                monkey,
+                reason,
+            } => write!(f, "round {round}, monkey {monkey}: {reason}"),
+            SimulationError::ProductOverflow => {
+                write!(
+                    f,
+                    "The product of the inspection counts doesn't fit into 128 bits"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+impl From<MonkeyError> for SimulationError {
+    fn from(error: MonkeyError) -> Self {
+        SimulationError::Monkey(error)
+    }
+}
+
+/// The fields every monkey needs besides the header
+const FIELDS: [&str; 5] = ["Starting items", "Operation", "Test", "If true", "If false"];
+
+/// Parses all monkeys. A line starting with `Monkey` begins the next monkey, its
+/// fields can come in any order and blank lines, indentation and CRLF don't matter.
+/// The monkeys are sorted by their number, which has to match their index.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_11::{parse_monkeys, EXAMPLE};
+///
+/// assert_eq!(parse_monkeys(EXAMPLE).unwrap().len(), 4);
+///
+/// let input = EXAMPLE.replace("divisible by 19", "divisible by nineteen");
+/// let error = parse_monkeys(&input).unwrap_err();
+/// assert_eq!(error.to_string(), "monkey 1, field `Test` (line 11): Invalid number");
+/// ```
+pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, MonkeyError> {
+    let mut blocks: Vec<Vec<(usize, &str)>> = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("Monkey") || blocks.is_empty() {
+            blocks.push(Vec::new());
+        }
+        blocks.last_mut().unwrap().push((index + 1, line));
+    }
+
+    let count = blocks.len();
+    let mut seen = vec![false; count];
+    let mut monkeys = Vec::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let monkey = Monkey::parse(index, block, count)?;
+        if std::mem::replace(&mut seen[monkey.name as usize], true) {
+            return Err(MonkeyError {
+                monkey: index,
+                field: "Monkey".to_string(),
+                line: block[0].0,
+                reason: "Duplicate monkey",
+            });
+        }
+        monkeys.push(monkey);
+    }
+    monkeys.sort_by_key(|m| m.name);
+    Ok(monkeys)
+}
+
 /// a monkey to handle the data
 impl Monkey {
-    /// converts a `Monkey` from a string
-    fn from_str(input: &str) -> Self {
-        let parts: Vec<&str> = input.split(|d| d == ':' || d == '\n').collect();
+    /// Parses the lines `(line number, text)` of the monkey at `index`, out of
+    /// `count` monkeys
+    fn parse(index: usize, lines: &[(usize, &str)], count: usize) -> Result<Self, MonkeyError> {
+        let error = |field: &str, line: usize, reason| MonkeyError {
+            monkey: index,
+            field: field.to_string(),
+            line,
+            reason,
+        };
+        let monkey_number = |field: &str, line: usize, value: &str| {
+            value
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| error(field, line, "Invalid number"))
+                .and_then(|name| {
+                    if (name as usize) < count {
+                        Ok(name)
+                    } else {
+                        Err(error(field, line, "There is no monkey with this number"))
+                    }
+                })
+        };
 
         // get the name
-        let name: Vec<&str> = parts[0].split_whitespace().collect();
-        let name = name[1].parse::<u8>().unwrap();
+        let (header_line, header) = lines[0];
+        let name = header
+            .strip_prefix("Monkey")
+            .and_then(|rest| rest.trim().strip_suffix(':'))
+            .ok_or(error(
+                "Monkey",
+                header_line,
+                "Expected a header like `Monkey 0:`",
+            ))?;
+        let name = monkey_number("Monkey", header_line, name)?;
+
+        // collect the fields by their key
+        let mut values: [Option<(usize, &str)>; FIELDS.len()] = [None; FIELDS.len()];
+        for &(line, text) in &lines[1..] {
+            let (key, value) = text.split_once(':').ok_or(error(
+                text,
+                line,
+                "Expected a field like `Test: ...`",
+            ))?;
+            let key = key.trim();
+            let field = FIELDS
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(key))
+                .ok_or(error(key, line, "Unknown field"))?;
+            if values[field].replace((line, value.trim())).is_some() {
+                return Err(error(key, line, "Duplicate field"));
+            }
+        }
+        let mut values = values.iter().zip(FIELDS).map(|(value, field)| {
+            value.map(|(line, value)| (field, line, value)).ok_or(error(
+                field,
+                header_line,
+                "Missing field",
+            ))
+        });
+        let mut next_field = || values.next().unwrap();
 
         // get the Starting Items
-        let cleared_spaces = parts[3].replace(" ", "").replace("\r", "");
-        let starting_items: Vec<u64> = cleared_spaces
-            .split(",")
-            .map(|s| s.parse::<u64>().unwrap())
-            .collect();
+        let (field, line, value) = next_field()?;
+        let items = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| item.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error(field, line, "Invalid number"))?;
 
         // get the operation
-        let operation = Operation::from_str(parts[5]);
+        let (field, line, value) = next_field()?;
+        let operation = Operation::from_str(value).map_err(|reason| error(field, line, reason))?;
 
         // get the test
-        let test = parts[7].split_whitespace().collect::<Vec<&str>>();
-        let test: u64 = test.last().unwrap().parse().unwrap();
+        let (field, line, value) = next_field()?;
+        let test = value
+            .strip_prefix("divisible by")
+            .ok_or(error(field, line, "Expected `divisible by N`"))?
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|&test| test > 0)
+            .ok_or(error(field, line, "Invalid number"))?;
 
         // get monkey options
-        let test_true = parts[9]
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .parse::<u8>()
-            .unwrap();
-
-        let test_false = parts[11]
-            .split_whitespace()
-            .last()
-            .unwrap()
-            .parse::<u8>()
-            .unwrap();
+        let mut target = || {
+            let (field, line, value) = next_field()?;
+            let value = value.strip_prefix("throw to monkey").ok_or(error(
+                field,
+                line,
+                "Expected `throw to monkey N`",
+            ))?;
+            monkey_number(field, line, value)
+        };
+        let test_true = target()?;
+        let test_false = target()?;
 
-        Self {
+        Ok(Self {
             name,
-            items: starting_items,
+            items,
             operation,
             test,
             throw_monkey: (test_true, test_false),
             inspected_count: 0,
-        }
+        })
+    }
+
+    /// the number of the monkey
+    pub fn name(&self) -> u8 {
+        self.name
+    }
+
+    /// the worry levels of the items the monkey holds
+    pub fn items(&self) -> &[u64] {
+        &self.items
     }
 
     // increase the inspection count
@@ -94,94 +695,558 @@ impl Monkey {
         self.inspected_count += 1;
     }
 
-    /// Proceeds a item with a given worry level
-    fn inspect_item(&mut self, item: u64, worry_behavior: fn(u64) -> u64) -> (u8, u64) {
-        let mut item = item;
+    /// Proceeds a item with a given worry level, the new worry level is reduced
+    /// by the `modulus` if there is one
+    fn inspect_item(
+        &mut self,
+        item: u64,
+        worry_behavior: &dyn WorryPolicy,
+        modulus: Option<u64>,
+    ) -> Result<(u8, u64), &'static str> {
         self.is_inspecting();
+        self.throw(item, worry_behavior, modulus)
+    }
 
-        match self.operation {
-            Operation::Add(option) => match option {
-                Some(value) => item += value,
-                None => item += item,
-            },
-            Operation::Multiply(option) => match option {
-                Some(value) => item *= value,
-                None => item *= item,
-            },
-        };
-
-        item = worry_behavior(item);
+    /// The monkey and the new worry level the item is thrown to, without counting
+    /// the inspection
+    fn throw(
+        &self,
+        item: u64,
+        worry_behavior: &dyn WorryPolicy,
+        modulus: Option<u64>,
+    ) -> Result<(u8, u64), &'static str> {
+        let mut worry = self.operation.evaluate(&(item as u128))?;
+        if let Some(modulus) = modulus {
+            worry %= modulus as u128;
+        }
+        let worry = u64::try_from(worry).map_err(|_| "The worry level doesn't fit into 64 bits")?;
+        let item = worry_behavior.relieve(self.name as usize, worry);
 
-        if item % self.test == 0 {
-            return (self.throw_monkey.0, item);
+        if item.is_multiple_of(self.test) {
+            return Ok((self.throw_monkey.0, item));
         }
-        return (self.throw_monkey.1, item);
+        Ok((self.throw_monkey.1, item))
     }
 
-    fn inspect_items(&mut self, worry_behavior: fn(u64) -> u64) -> Vec<(u8, u64)> {
+    fn inspect_items(
+        &mut self,
+        worry_behavior: &dyn WorryPolicy,
+        modulus: Option<u64>,
+    ) -> Result<Vec<(u8, u64)>, &'static str> {
         let items = std::mem::take(&mut self.items);
 
         // Process all items
         items
             .into_iter()
-            .map(|item| self.inspect_item(item, worry_behavior))
+            .map(|item| self.inspect_item(item, worry_behavior, modulus))
             .collect()
     }
 
+    /// Like `inspect_item` with the exact worry level
+    fn inspect_item_exact(
+        &mut self,
+        item: &BigUint,
+        worry_behavior: &dyn WorryPolicy,
+    ) -> Result<(u8, BigUint), &'static str> {
+        self.is_inspecting();
+
+        let worry = self.operation.evaluate(item)?;
+        let item = worry_behavior
+            .relieve_exact(self.name as usize, worry)
+            .ok_or("The worry level doesn't fit into 64 bits for the policy")?;
+
+        if item.rem_u64(self.test) == 0 {
+            return Ok((self.throw_monkey.0, item));
+        }
+        Ok((self.throw_monkey.1, item))
+    }
+
     fn push_item(&mut self, item: u64) {
         self.items.push(item);
     }
 }
 
+/// How the worry level changes after a monkey inspected an item and got bored.
+/// Every `Fn(u64) -> u64` is a policy for all monkeys.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_11::{solve_stars, CapAt, EXAMPLE};
+///
+/// assert_eq!(solve_stars(EXAMPLE, 20, |worry| worry / 3), 10605);
+/// assert_eq!(solve_stars(EXAMPLE, 20, CapAt(1000)), 100 * 100);
+/// ```
+pub trait WorryPolicy {
+    /// the new worry level after the monkey with the number inspected the item
+    fn relieve(&self, monkey: usize, worry: u64) -> u64;
+
+    /// `true` if the policy keeps the remainder of the worry level for any divisor,
+    /// only then the worry levels can be reduced by the tests. `false` by default.
+    fn keeps_remainders(&self) -> bool {
+        false
+    }
+
+    /// Like `relieve` with an exact worry level of any size for `solve_stars_exact`.
+    /// By default the worry level has to fit into 64 bits, `None` if it doesn't.
+    fn relieve_exact(&self, monkey: usize, worry: BigUint) -> Option<BigUint> {
+        let worry = worry.to_u64()?;
+        Some(BigUint::from_u64(self.relieve(monkey, worry)))
+    }
+}
+
+impl<F: Fn(u64) -> u64> WorryPolicy for F {
+    fn relieve(&self, _monkey: usize, worry: u64) -> u64 {
+        self(worry)
+    }
+}
+
+/// Keeps the worry level as it is, that is part two
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unchanged;
+
+impl WorryPolicy for Unchanged {
+    fn relieve(&self, _monkey: usize, worry: u64) -> u64 {
+        worry
+    }
+
+    fn keeps_remainders(&self) -> bool {
+        true
+    }
+
+    fn relieve_exact(&self, _monkey: usize, worry: BigUint) -> Option<BigUint> {
+        Some(worry)
+    }
+}
+
+/// Divides the worry level, `DivideBy::new(3)` is part one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DivideBy(NonZeroU64);
+
+impl DivideBy {
+    /// `None` if the divisor is zero
+    pub fn new(divisor: u64) -> Option<Self> {
+        NonZeroU64::new(divisor).map(DivideBy)
+    }
+
+    pub fn divisor(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl WorryPolicy for DivideBy {
+    fn relieve(&self, _monkey: usize, worry: u64) -> u64 {
+        worry / self.0
+    }
+
+    fn keeps_remainders(&self) -> bool {
+        self.divisor() == 1
+    }
+
+    fn relieve_exact(&self, _monkey: usize, worry: BigUint) -> Option<BigUint> {
+        let (quotient, _) = worry.div_rem(&BigUint::from_u64(self.divisor()))?;
+        Some(quotient)
+    }
+}
+
+/// Keeps the worry level from growing past the cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapAt(pub u64);
+
+impl WorryPolicy for CapAt {
+    fn relieve(&self, _monkey: usize, worry: u64) -> u64 {
+        worry.min(self.0)
+    }
+
+    fn relieve_exact(&self, _monkey: usize, worry: BigUint) -> Option<BigUint> {
+        Some(worry.min(BigUint::from_u64(self.0)))
+    }
+}
+
+/// A policy for every monkey by its number, the worry level stays the same for
+/// monkeys without a policy
+#[derive(Default)]
+pub struct PerMonkey(pub Vec<Box<dyn WorryPolicy>>);
+
+impl WorryPolicy for PerMonkey {
+    fn relieve(&self, monkey: usize, worry: u64) -> u64 {
+        self.0
+            .get(monkey)
+            .map_or(worry, |policy| policy.relieve(monkey, worry))
+    }
+
+    fn keeps_remainders(&self) -> bool {
+        self.0.iter().all(|policy| policy.keeps_remainders())
+    }
+
+    fn relieve_exact(&self, monkey: usize, worry: BigUint) -> Option<BigUint> {
+        match self.0.get(monkey) {
+            Some(policy) => policy.relieve_exact(monkey, worry),
+            None => Some(worry),
+        }
+    }
+}
+
 pub fn worry_behavior_div_3(item: u64) -> u64 {
     item / 3
 }
 
+/// The worry level stays the same. A function can't tell that it keeps the remainders,
+/// so the worry levels aren't reduced with it, use `Unchanged` for part two.
 pub fn worry_behavior_none(item: u64) -> u64 {
     item
 }
 
-pub fn solve_stars(input: &str, rounds: usize, worry_behavior: fn(u64) -> u64) -> u64 {
-    let blocks = split_blocks(&input);
-    let mut monkeys: Vec<Monkey> = Vec::new();
+/// Simulates the monkeys and returns the product of the two highest inspection counts.
+/// Panics if the input is invalid or a worry level can't be calculated, see
+/// `solve_stars_top` for the errors.
+///
+/// If all operations only add and multiply and the behavior keeps the remainders, like
+/// `Unchanged`, the worry levels are reduced modulo the least common multiple
+/// of the tests after every inspection. Otherwise they stay exact and the simulation
+/// fails once they don't fit into 64 bits, see `solve_stars_exact` for larger ones.
+pub fn solve_stars(input: &str, rounds: usize, worry_behavior: impl WorryPolicy) -> u64 {
+    let business =
+        solve_stars_top(input, rounds, worry_behavior, 2).unwrap_or_else(|error| panic!("{error}"));
+    u64::try_from(business).expect("The monkey business doesn't fit into 64 bits")
+}
 
-    // parse the monkeys
-    for block in blocks {
-        let monkey = Monkey::from_str(&block);
-        monkeys.push(monkey);
+/// Like `solve_stars` with the product of the `top` highest inspection counts
+pub fn solve_stars_top(
+    input: &str,
+    rounds: usize,
+    worry_behavior: impl WorryPolicy,
+    top: usize,
+) -> Result<u128, SimulationError> {
+    let mut monkeys = parse_monkeys(input)?;
+    let modulus = reduction(&monkeys, &worry_behavior);
+
+    // lets start
+    for round in 1..=rounds {
+        play_round(&mut monkeys, &worry_behavior, modulus, round, |_, _| ())?;
     }
 
-    // calculate the product of all test divisors
-    let modulo: u64 = monkeys.iter().map(|m| m.test).product();
+    let counts: Vec<u64> = monkeys.iter().map(|m| m.inspected_count as u64).collect();
+    top_product(&counts, top).ok_or(SimulationError::ProductOverflow)
+}
 
-    // sort monkey, so that the index equal to the name
-    monkeys.sort_by_key(|m| m.name);
+/// The product of the `top` highest inspection counts, the monkey business for two.
+/// `None` if it doesn't fit into 128 bits.
+pub fn top_product(counts: &[u64], top: usize) -> Option<u128> {
+    let mut counts = counts.to_vec();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts
+        .iter()
+        .take(top)
+        .try_fold(1u128, |product, &count| product.checked_mul(count as u128))
+}
 
-    // lets start
-    for _ in 0..rounds {
-        for i in 0..monkeys.len() {
-            let changes = {
-                // get the current monkey
-                let monk = &mut monkeys[i];
-                monk.inspect_items(worry_behavior)
-            };
-
-            // Apply modulo to each item before passing it on
-            let changes = changes
-                .into_iter()
-                .map(|(target, item)| (target, item % modulo))
-                .collect::<Vec<_>>();
-
-            for (target, item) in changes {
-                monkeys[target as usize].push_item(item);
+/// Plays the round with the number, `on_throw` sees every throw as `(from, to)`
+fn play_round(
+    monkeys: &mut [Monkey],
+    worry_behavior: &dyn WorryPolicy,
+    modulus: Option<u64>,
+    round: usize,
+    mut on_throw: impl FnMut(usize, usize),
+) -> Result<(), SimulationError> {
+    for i in 0..monkeys.len() {
+        let changes = monkeys[i]
+            .inspect_items(worry_behavior, modulus)
+            .map_err(|reason| SimulationError::Worry {
+                round,
+                monkey: monkeys[i].name,
+                reason,
+            })?;
+        for (target, item) in changes {
+            on_throw(i, target as usize);
+            monkeys[target as usize].push_item(item);
+        }
+    }
+    Ok(())
+}
+
+/// What happened in one round of `simulate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Round {
+    /// the number of the round, starting with 1
+    pub number: usize,
+    /// the worry levels of the items every monkey holds after the round
+    pub items: Vec<Vec<u64>>,
+    /// how many items every monkey inspected in this round
+    pub inspections: Vec<u64>,
+    /// `throws[from][to]` is how many items `from` threw to `to` in this round
+    pub throws: Vec<Vec<u64>>,
+}
+
+/// Every round of a simulation, created by `simulate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    pub rounds: Vec<Round>,
+}
+
+impl History {
+    /// how many items every monkey inspected from the first round up to the round
+    pub fn inspections_until(&self, round: usize) -> Vec<u64> {
+        let mut totals = vec![0; self.rounds.first().map_or(0, |r| r.inspections.len())];
+        for played in self.rounds.iter().take(round) {
+            for (total, count) in totals.iter_mut().zip(&played.inspections) {
+                *total += count;
+            }
+        }
+        totals
+    }
+
+    /// `graph[from][to]` is how many items `from` threw to `to` in all rounds
+    pub fn throw_graph(&self) -> Vec<Vec<u64>> {
+        let count = self.rounds.first().map_or(0, |r| r.throws.len());
+        let mut graph = vec![vec![0; count]; count];
+        for round in &self.rounds {
+            for (from, targets) in round.throws.iter().enumerate() {
+                for (to, count) in targets.iter().enumerate() {
+                    graph[from][to] += count;
+                }
+            }
+        }
+        graph
+    }
+
+    /// the product of the two highest inspection counts after the last round
+    pub fn monkey_business(&self) -> u128 {
+        top_product(&self.inspections_until(self.rounds.len()), 2)
+            .expect("The product of two counts fits into 128 bits")
+    }
+
+    /// Lists the inspection counts after the rounds like the puzzle
+    /// ```text
+    /// == After round 1 ==
+    /// Monkey 0 inspected items 2 times.
+    /// ```
+    pub fn render_inspections(&self, rounds: &[usize]) -> String {
+        let blocks: Vec<String> = rounds
+            .iter()
+            .map(|&round| {
+                let mut block = format!("== After round {round} ==\n");
+                for (monkey, count) in self.inspections_until(round).iter().enumerate() {
+                    block.push_str(&format!("Monkey {monkey} inspected items {count} times.\n"));
+                }
+                block
+            })
+            .collect();
+        blocks.join("\n")
+    }
+
+    /// Lists the items the monkeys hold after the rounds like the puzzle
+    /// ```text
+    /// After round 1, the monkeys are holding items with these worry levels:
+    /// Monkey 0: 20, 23, 27, 26
+    /// ```
+    pub fn render_items(&self, rounds: &[usize]) -> String {
+        let blocks: Vec<String> = rounds
+            .iter()
+            .filter_map(|&round| self.rounds.get(round.checked_sub(1)?))
+            .map(|round| {
+                let mut block = format!(
+                    "After round {}, the monkeys are holding items with these worry levels:\n",
+                    round.number
+                );
+                for (monkey, items) in round.items.iter().enumerate() {
+                    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                    block.push_str(&format!("Monkey {monkey}: {}\n", items.join(", ")));
+                }
+                block
+            })
+            .collect();
+        blocks.join("\n")
+    }
+
+    /// Exports the throw graph in the DOT format of Graphviz, every edge is labeled
+    /// with the number of throws
+    pub fn throw_graph_to_dot(&self) -> String {
+        let mut out = String::from("digraph throws {\n");
+        for (from, targets) in self.throw_graph().iter().enumerate() {
+            for (to, count) in targets.iter().enumerate().filter(|(_, &count)| count > 0) {
+                out.push_str(&format!("    {from} -> {to} [label={count}];\n"));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Simulates the monkeys like `solve_stars` and records every round
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_11::{simulate, worry_behavior_div_3, EXAMPLE};
+///
+/// let history = simulate(EXAMPLE, 20, worry_behavior_div_3).unwrap();
+/// assert_eq!(history.rounds[0].items[1], [2080, 25, 167, 207, 401, 1046]);
+/// assert_eq!(history.monkey_business(), 10605);
+/// ```
+pub fn simulate(
+    input: &str,
+    rounds: usize,
+    worry_behavior: impl WorryPolicy,
+) -> Result<History, SimulationError> {
+    let mut monkeys = parse_monkeys(input)?;
+    let modulus = reduction(&monkeys, &worry_behavior);
+    let count = monkeys.len();
+
+    let mut history = History { rounds: Vec::new() };
+    for number in 1..=rounds {
+        let before: Vec<u32> = monkeys.iter().map(|m| m.inspected_count).collect();
+        let mut throws = vec![vec![0; count]; count];
+        play_round(
+            &mut monkeys,
+            &worry_behavior,
+            modulus,
+            number,
+            |from, to| {
+                throws[from][to] += 1;
+            },
+        )?;
+        history.rounds.push(Round {
+            number,
+            items: monkeys.iter().map(|m| m.items.clone()).collect(),
+            inspections: monkeys
+                .iter()
+                .zip(before)
+                .map(|(m, before)| (m.inspected_count - before) as u64)
+                .collect(),
+            throws,
+        });
+    }
+    Ok(history)
+}
+
+/// Counts how often every monkey inspects items in `rounds` rounds, with the same
+/// worry levels as `solve_stars`.
+///
+/// Every item is followed on its own: its monkey and worry level at the start of a
+/// round decide the next round, and there are only so many of those states. Once
+/// a state repeats, the inspections of the cycle are multiplied up to `rounds`, so
+/// even 10^12 rounds don't need to be simulated. That needs the reduced worry levels
+/// of `solve_stars`, exact ones only repeat if the behavior keeps them small.
+/// `fast_forward` fails like `solve_stars_top` if they don't.
+pub fn fast_forward(
+    input: &str,
+    rounds: u64,
+    worry_behavior: impl WorryPolicy,
+) -> Result<Vec<u64>, SimulationError> {
+    let monkeys = parse_monkeys(input)?;
+    let modulus = reduction(&monkeys, &worry_behavior);
+    let mut totals = vec![0; monkeys.len()];
+
+    for (start, monkey) in monkeys.iter().enumerate() {
+        for &item in &monkey.items {
+            let mut state = (start, modulus.map_or(item, |modulus| item % modulus));
+            // the round in which a state was seen first
+            let mut seen: HashMap<(usize, u64), u64> = HashMap::new();
+            // prefix[r] are the inspections of this item in the first `r` rounds
+            let mut prefix: Vec<Vec<u64>> = vec![vec![0; monkeys.len()]];
+
+            let mut round = 0;
+            while round < rounds {
+                if let Some(&cycle_start) = seen.get(&state) {
+                    let length = round - cycle_start;
+                    let (cycles, rest) = (
+                        (rounds - cycle_start) / length,
+                        (rounds - cycle_start) % length,
+                    );
+                    let at = |r: u64| &prefix[r as usize];
+                    for (monkey, total) in totals.iter_mut().enumerate() {
+                        let per_cycle = at(round)[monkey] - at(cycle_start)[monkey];
+                        let partial = at(cycle_start + rest)[monkey] - at(cycle_start)[monkey];
+                        *total += at(cycle_start)[monkey] + cycles * per_cycle + partial;
+                    }
+                    break;
+                }
+                seen.insert(state, round);
+
+                let mut counts = prefix[round as usize].clone();
+                let (mut current, mut worry) = state;
+                // the item moves on in the same round if it lands at a later monkey
+                loop {
+                    counts[current] += 1;
+                    let (target, next) = monkeys[current]
+                        .throw(worry, &worry_behavior, modulus)
+                        .map_err(|reason| SimulationError::Worry {
+                            round: round as usize + 1,
+                            monkey: monkeys[current].name,
+                            reason,
+                        })?;
+                    let target = target as usize;
+                    worry = next;
+                    let later = target > current;
+                    current = target;
+                    if !later {
+                        break;
+                    }
+                }
+                state = (current, worry);
+                prefix.push(counts);
+                round += 1;
+            }
+            if round == rounds {
+                for (total, count) in totals.iter_mut().zip(&prefix[round as usize]) {
+                    *total += count;
+                }
             }
         }
     }
+    Ok(totals)
+}
+
+/// Like `solve_stars` for any number of rounds, see `fast_forward`
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_11::{solve_stars_fast, Unchanged, EXAMPLE};
+///
+/// let business = solve_stars_fast(EXAMPLE, 10_000, Unchanged);
+/// assert_eq!(business, Ok(2713310158));
+/// ```
+pub fn solve_stars_fast(
+    input: &str,
+    rounds: u64,
+    worry_behavior: impl WorryPolicy,
+) -> Result<u128, SimulationError> {
+    top_product(&fast_forward(input, rounds, worry_behavior)?, 2)
+        .ok_or(SimulationError::ProductOverflow)
+}
+
+/// Like `solve_stars` with exact worry levels of any size, see
+/// `WorryPolicy::relieve_exact`. Without a relief the worry levels grow
+/// exponentially, so this only works for a few rounds then.
+pub fn solve_stars_exact(
+    input: &str,
+    rounds: usize,
+    worry_behavior: impl WorryPolicy,
+) -> Result<u128, SimulationError> {
+    let mut monkeys = parse_monkeys(input)?;
+    let mut items: Vec<Vec<BigUint>> = monkeys
+        .iter()
+        .map(|m| {
+            m.items
+                .iter()
+                .map(|&item| BigUint::from_u64(item))
+                .collect()
+        })
+        .collect();
 
-    monkeys.sort_by_key(|m| -(m.inspected_count as i32));
+    for round in 1..=rounds {
+        for i in 0..monkeys.len() {
+            for item in std::mem::take(&mut items[i]) {
+                let (target, item) = monkeys[i]
+                    .inspect_item_exact(&item, &worry_behavior)
+                    .map_err(|reason| SimulationError::Worry {
+                        round,
+                        monkey: monkeys[i].name,
+                        reason,
+                    })?;
+                items[target as usize].push(item);
+            }
+        }
+    }
 
-    // Cast to u64 for the final result
-    (monkeys[0].inspected_count as u64) * (monkeys[1].inspected_count as u64)
+    let counts: Vec<u64> = monkeys.iter().map(|m| m.inspected_count as u64).collect();
+    top_product(&counts, 2).ok_or(SimulationError::ProductOverflow)
 }
 
 pub const EXAMPLE: &str = "\
@@ -219,21 +1284,66 @@ mod tests_day_11 {
 
     use super::*;
 
+    fn binary(operator: Operator, left: Operation, right: Operation) -> Operation {
+        Operation::Binary(operator, Box::new(left), Box::new(right))
+    }
+
     #[test]
     fn test_operation_from_str() {
-        // first test with int
-        let input = "new = old * 19";
-        let result = Operation::from_str(&input);
-        let expected = Operation::Multiply(Some(19));
-        assert_eq!(result, expected);
+        let result = Operation::from_str("new = old * 19");
+        let expected = binary(Operator::Multiply, Operation::Old, Operation::Value(19));
+        assert_eq!(result, Ok(expected));
 
         let result = Operation::from_str("new = old * old");
-        let expected = Operation::Multiply(None);
-        assert_eq!(result, expected);
+        let expected = binary(Operator::Multiply, Operation::Old, Operation::Old);
+        assert_eq!(result, Ok(expected));
 
-        let result = Operation::from_str("new = old + 3");
-        let expected = Operation::Add(Some(3));
-        assert_eq!(result, expected);
+        let result = Operation::from_str(" new = 3 + old");
+        let expected = binary(Operator::Add, Operation::Value(3), Operation::Old);
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test]
+    fn test_operation_precedence() {
+        let evaluate = |s: &str, old: u128| Operation::from_str(s).unwrap().evaluate(&old);
+        assert_eq!(evaluate("new = old + 2 * 3", 1), Ok(7));
+        assert_eq!(evaluate("new = (old + 2) * 3", 1), Ok(9));
+        assert_eq!(evaluate("new = 10 - old - 2", 3), Ok(5));
+        assert_eq!(evaluate("new = old / 2 / 2", 20), Ok(5));
+        assert_eq!(evaluate("new = old * old % 7", 4), Ok(2));
+        assert_eq!(evaluate("new=((old))-(1+old%3)", 8), Ok(5));
+        assert_eq!(evaluate("new = old - 5", 3), Err(BELOW_ZERO));
+        assert_eq!(evaluate("new = 4 / (old - 1)", 1), Err(DIVISION_BY_ZERO));
+        assert_eq!(evaluate("new = old * old", u128::MAX), Err(TOO_LARGE));
+    }
+
+    #[test]
+    fn test_operation_errors() {
+        let error = |s: &str| Operation::from_str(s).unwrap_err();
+        assert_eq!(error("old * 3"), "The operation has to start with `new =`");
+        assert_eq!(error("new = old *"), "The operation ends too early");
+        assert_eq!(error("new = (old + 1"), "Missing closing parenthesis");
+        assert_eq!(error("new = old 3"), "Unexpected token after the operation");
+        assert_eq!(error("new = old ^ 2"), "Invalid character in the operation");
+        assert_eq!(error("new = older + 1"), "Invalid number");
+        assert_eq!(error("new = * 2"), "Expected `old`, a number or `(`");
+
+        // deep operations are rejected before they overflow the stack
+        let nested = |depth: usize| format!("new = {}old{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Operation::from_str(&nested(100)).is_ok());
+        assert_eq!(error(&nested(200_000)), TOO_DEEP);
+        let chain = |length: usize| format!("new = old{}", " - 1".repeat(length));
+        let operation = Operation::from_str(&chain(MAX_DEPTH)).unwrap();
+        assert_eq!(operation.evaluate(&1000u128), Ok(1000 - MAX_DEPTH as u128));
+        assert_eq!(error(&chain(MAX_DEPTH + 1)), TOO_DEEP);
+        assert_eq!(error(&chain(200_000)), TOO_DEEP);
+        let right = |length: usize| format!("new = {}old", "1 + 2 * (".repeat(length));
+        assert_eq!(error(&right(200_000)), TOO_DEEP);
+
+        // and show up as an error of the monkey
+        let input = EXAMPLE.replace("new = old * 19", &nested(200_000));
+        let error = parse_monkeys(&input).unwrap_err();
+        assert_eq!((error.monkey, error.reason), (0, TOO_DEEP));
     }
 
     #[test]
@@ -246,11 +1356,12 @@ Monkey 0:
     If true: throw to monkey 1
     If false: throw to monkey 4";
 
-        let result = Monkey::from_str(&input);
+        let lines: Vec<(usize, &str)> = input.lines().map(str::trim).enumerate().collect();
+        let result = Monkey::parse(0, &lines, 5).unwrap();
         let expected = Monkey {
             name: 0,
             items: [66, 59, 64, 51].to_vec(),
-            operation: Operation::Multiply(Some(3)),
+            operation: binary(Operator::Multiply, Operation::Old, Operation::Value(3)),
             test: 2,
             inspected_count: 0,
             throw_monkey: (1, 4),
@@ -275,8 +1386,426 @@ Monkey 0:
     #[test]
     fn test_star_two() {
         let input = load_input(11);
-        let result = solve_stars(&input, 10000, worry_behavior_none);
+        let result = solve_stars(&input, 10000, Unchanged);
 
         assert_eq!(result, 18170818354);
     }
+
+    #[test]
+    fn test_modified_operations() {
+        let input = EXAMPLE
+            .replace("new = old * 19", "new = (old + 1) * 19")
+            .replace("new = old + 6", "new = 6 + old * 2");
+        let result = solve_stars(&input, 1000, Unchanged);
+        assert_eq!(result, 25005000);
+    }
+
+    #[test]
+    fn test_parse_monkeys_layout() {
+        let input = "\r
+Monkey 1:\r
+  If false: throw to monkey 0\r
+  Test: divisible by 3\r
+  Starting items:\r
+\r
+\r
+  If true: throw to monkey 0\r
+  Operation: new = old + 1\r
+Monkey 0:\r
+\tStarting items: 5,6 ,  7\r
+\tOperation:   new = old * old\r
+\tTest: divisible by 2\r
+\tIf true: throw to monkey 1\r
+\tIf false: throw to monkey 1\r
+";
+        let monkeys = parse_monkeys(input).unwrap();
+        assert_eq!(monkeys.len(), 2);
+        assert_eq!(monkeys[0].items(), [5, 6, 7]);
+        assert_eq!(monkeys[1].name(), 1);
+        assert_eq!(monkeys[1].items(), []);
+        assert_eq!(monkeys[1].throw_monkey, (0, 0));
+    }
+
+    #[test]
+    fn test_parse_monkeys_errors() {
+        let error = |input: &str| {
+            let error = parse_monkeys(input).unwrap_err();
+            (error.monkey, error.field, error.line, error.reason)
+        };
+        let broken = |from: &str, to: &str| error(&EXAMPLE.replace(from, to));
+
+        assert_eq!(
+            broken("new = old + 6", "new = old ^ 6"),
+            (
+                1,
+                "Operation".to_string(),
+                10,
+                "Invalid character in the operation"
+            )
+        );
+        assert_eq!(
+            broken("79, 60, 97", "79, x, 97"),
+            (2, "Starting items".to_string(), 16, "Invalid number")
+        );
+        assert_eq!(
+            broken("  Test: divisible by 17\n", ""),
+            (3, "Test".to_string(), 22, "Missing field")
+        );
+        assert_eq!(
+            broken("If true: throw to monkey 1", "If true: throw to monkey 7"),
+            (
+                2,
+                "If true".to_string(),
+                19,
+                "There is no monkey with this number"
+            )
+        );
+        assert_eq!(
+            broken("Test: divisible by 19", "Tset: divisible by 19"),
+            (1, "Tset".to_string(), 11, "Unknown field")
+        );
+        assert_eq!(
+            broken("Monkey 3:", "Monkey 1:"),
+            (3, "Monkey".to_string(), 22, "Duplicate monkey")
+        );
+        assert_eq!(
+            broken("Test: divisible by 13", "Test: divisible by 0"),
+            (2, "Test".to_string(), 18, "Invalid number")
+        );
+        assert_eq!(
+            broken("If false: throw to monkey 0", "If false: throw to 0"),
+            (
+                1,
+                "If false".to_string(),
+                13,
+                "Expected `throw to monkey N`"
+            )
+        );
+        assert_eq!(
+            error("  Starting items: 1\nMonkey 0:"),
+            (
+                0,
+                "Monkey".to_string(),
+                1,
+                "Expected a header like `Monkey 0:`"
+            )
+        );
+    }
+
+    #[test]
+    fn test_big_uint() {
+        let big = |value: u128| {
+            let low = BigUint::from_u64(value as u64);
+            let high = BigUint::from_u64((value >> 64) as u64);
+            high.mul(&BigUint::from_u64(1 << 32))
+                .mul(&BigUint::from_u64(1 << 32))
+                .add(&low)
+        };
+        let values = [
+            0,
+            1,
+            7,
+            1 << 40,
+            u64::MAX as u128,
+            123_456_789_012_345_678_901,
+        ];
+        for a in values {
+            for b in values {
+                assert_eq!(big(a).add(&big(b)), big(a + b));
+                assert_eq!(big(a).sub(&big(b)), a.checked_sub(b).map(big));
+                if let Some(product) = a.checked_mul(b) {
+                    assert_eq!(big(a).mul(&big(b)), big(product));
+                }
+                let division = big(a).div_rem(&big(b));
+                let expected = a.checked_div(b).map(|q| (big(q), big(a % b)));
+                assert_eq!(division, expected);
+                assert_eq!(big(a).cmp(&big(b)), a.cmp(&b));
+            }
+            assert_eq!(big(a).to_string(), a.to_string());
+            assert_eq!(big(a).rem_u64(1_000_003), (a % 1_000_003) as u64);
+        }
+        let power = (0..4).fold(big(1), |power, _| power.mul(&big(1 << 50)));
+        assert_eq!(
+            power.to_string(),
+            "1606938044258990275541962092341162602522202993782792835301376"
+        );
+    }
+
+    #[test]
+    fn test_modulus() {
+        let monkeys = parse_monkeys(&EXAMPLE.replace("by 23", "by 26")).unwrap();
+        assert_eq!(modulus(&monkeys), Some(26 * 19 * 17));
+        assert_eq!(reduction(&monkeys, &Unchanged), Some(26 * 19 * 17));
+        assert_eq!(
+            reduction(&monkeys, &PerMonkey::default()),
+            Some(26 * 19 * 17)
+        );
+        assert_eq!(reduction(&monkeys, &worry_behavior_div_3), None);
+        assert_eq!(reduction(&monkeys, &CapAt(1000)), None);
+
+        // a function keeps the exact worry levels, which soon don't fit anymore
+        assert_eq!(reduction(&monkeys, &worry_behavior_none), None);
+        let error = solve_stars_top(EXAMPLE, 20, worry_behavior_none, 2).unwrap_err();
+        assert!(matches!(
+            error,
+            SimulationError::Worry {
+                reason: "The worry level doesn't fit into 64 bits" | TOO_LARGE,
+                ..
+            }
+        ));
+        // while they fit the result is the same
+        assert_eq!(
+            solve_stars(EXAMPLE, 3, worry_behavior_none),
+            solve_stars(EXAMPLE, 3, Unchanged)
+        );
+    }
+
+    #[test]
+    fn test_stars_exact() {
+        let div_3 = DivideBy::new(3).unwrap();
+        assert_eq!(solve_stars_exact(EXAMPLE, 20, div_3), Ok(10605));
+        let input = EXAMPLE.replace("new = old * 19", "new = (old + 1) * 19");
+        assert_eq!(solve_stars_exact(&input, 20, div_3), Ok(10710));
+        assert_eq!(solve_stars_exact(EXAMPLE, 20, Unchanged), Ok(99 * 103));
+        assert_eq!(solve_stars_exact(EXAMPLE, 20, CapAt(1000)), Ok(100 * 100));
+        // a function only gets worry levels that fit into 64 bits
+        let error = solve_stars_exact(EXAMPLE, 20, |worry| worry).unwrap_err();
+        assert!(matches!(
+            error,
+            SimulationError::Worry {
+                reason: "The worry level doesn't fit into 64 bits for the policy",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_operations_without_reduction() {
+        // a remainder or a division only works with the exact worry levels
+        let input = EXAMPLE.replace("new = old * old", "new = old * old % 1000003");
+        let monkeys = parse_monkeys(&input).unwrap();
+        assert_eq!(reduction(&monkeys, &Unchanged), None);
+        let exact = solve_stars_exact(&input, 1000, DivideBy::new(3).unwrap()).unwrap();
+        assert_eq!(
+            solve_stars_top(&input, 1000, worry_behavior_div_3, 2),
+            Ok(exact)
+        );
+        assert_eq!(
+            fast_forward(&input, 1000, worry_behavior_div_3).unwrap(),
+            simulate(&input, 1000, worry_behavior_div_3)
+                .unwrap()
+                .inspections_until(1000)
+        );
+
+        let input = EXAMPLE.replace("new = old + 6", "new = (old + 6) / 2");
+        let exact = solve_stars_exact(&input, 20, DivideBy::new(3).unwrap()).unwrap();
+        assert_eq!(
+            solve_stars_top(&input, 20, worry_behavior_div_3, 2),
+            Ok(exact)
+        );
+
+        // going below zero is an error instead of a panic
+        let input = EXAMPLE.replace("new = old + 3", "new = old - 80");
+        assert_eq!(
+            solve_stars_top(&input, 20, Unchanged, 2),
+            Err(SimulationError::Worry {
+                round: 1,
+                monkey: 3,
+                reason: BELOW_ZERO
+            })
+        );
+        // as is a worry level that doesn't fit
+        let input = EXAMPLE.replace("new = old + 3", "new = old * old - 1");
+        let error = simulate(&input, 20, Unchanged).unwrap_err();
+        assert!(matches!(
+            error,
+            SimulationError::Worry {
+                reason: "The worry level doesn't fit into 64 bits" | TOO_LARGE,
+                ..
+            }
+        ));
+
+        let error = solve_stars_top("Monkey 0:", 20, Unchanged, 2).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "monkey 0, field `Starting items` (line 1): Missing field"
+        );
+    }
+
+    #[test]
+    fn test_large_worry_levels() {
+        // the product of the tests and the squares don't fit into 64 bits
+        let input = "\
+Monkey 0:
+  Starting items: 3000000000000, 2199023255551
+  Operation: new = old * old
+  Test: divisible by 1099511627776
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items:
+  Operation: new = old + 1099511627775
+  Test: divisible by 2199023255552
+    If true: throw to monkey 0
+    If false: throw to monkey 0";
+        assert_eq!(solve_stars(input, 100, Unchanged), 200 * 200);
+    }
+
+    #[test]
+    fn test_simulate_render() {
+        let description = include_str!("descriptions/day_11.md");
+        let history = simulate(EXAMPLE, 20, worry_behavior_div_3).unwrap();
+        let expected = "\
+After round 1, the monkeys are holding items with these worry levels:
+Monkey 0: 20, 23, 27, 26
+Monkey 1: 2080, 25, 167, 207, 401, 1046
+Monkey 2: \nMonkey 3: \n";
+        assert_eq!(history.render_items(&[1]), expected);
+        // the rounds 2 to 10 follow one after another in the description
+        let rounds: Vec<usize> = (2..=10).collect();
+        assert!(description.contains(&history.render_items(&rounds)));
+        assert!(description.contains(&history.render_items(&[20])));
+
+        let history = simulate(EXAMPLE, 1000, Unchanged).unwrap();
+        assert!(description.contains(&history.render_inspections(&[1, 20, 1000])));
+        assert_eq!(history.monkey_business(), 5204 * 5192);
+    }
+
+    #[test]
+    fn test_throw_graph() {
+        let history = simulate(EXAMPLE, 1, worry_behavior_div_3).unwrap();
+        assert_eq!(history.rounds[0].inspections, vec![2, 4, 3, 5]);
+        assert_eq!(
+            history.throw_graph(),
+            vec![
+                vec![0, 0, 0, 2],
+                vec![4, 0, 0, 0],
+                vec![0, 1, 0, 2],
+                vec![0, 5, 0, 0],
+            ]
+        );
+        let expected = "\
+digraph throws {
+    0 -> 3 [label=2];
+    1 -> 0 [label=4];
+    2 -> 1 [label=1];
+    2 -> 3 [label=2];
+    3 -> 1 [label=5];
+}
+";
+        assert_eq!(history.throw_graph_to_dot(), expected);
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        let input = load_input(11);
+        assert_eq!(fast_forward(&input, 0, Unchanged), Ok(vec![0; 8]));
+        for rounds in [1, 20, 333, 1000, 2500] {
+            let expected = simulate(&input, rounds, Unchanged)
+                .unwrap()
+                .inspections_until(rounds);
+            assert_eq!(fast_forward(&input, rounds as u64, Unchanged), Ok(expected));
+        }
+        let expected = simulate(&input, 20, worry_behavior_div_3)
+            .unwrap()
+            .inspections_until(20);
+        assert_eq!(fast_forward(&input, 20, worry_behavior_div_3), Ok(expected));
+
+        assert_eq!(solve_stars_fast(&input, 10_000, Unchanged), Ok(18170818354));
+    }
+
+    #[test]
+    fn test_fast_forward_huge() {
+        // every item of the example runs into a cycle of 171 or 448 rounds within the
+        // first 175 rounds, so all of them repeat after lcm(171, 448) = 76608 rounds
+        let (start, period) = (200, 76608);
+        let history = simulate(EXAMPLE, start + 2 * period, Unchanged).unwrap();
+        // the same items at the start and the end of a period prove the repetition
+        assert_eq!(
+            history.rounds[start - 1].items,
+            history.rounds[start + period - 1].items
+        );
+        assert_eq!(
+            fast_forward(EXAMPLE, (start + 2 * period) as u64, Unchanged),
+            Ok(history.inspections_until(start + 2 * period))
+        );
+
+        // extrapolate the simulation to 10^12 rounds
+        let rounds = 1_000_000_000_000;
+        let cycles = (rounds - start as u64) / period as u64;
+        let rest = (rounds - start as u64) as usize % period;
+        let before = history.inspections_until(start);
+        let cycle = history.inspections_until(start + period);
+        let partial = history.inspections_until(start + rest);
+        let expected: Vec<u64> = (0..4)
+            .map(|monkey| {
+                before[monkey]
+                    + cycles * (cycle[monkey] - before[monkey])
+                    + (partial[monkey] - before[monkey])
+            })
+            .collect();
+        assert_eq!(fast_forward(EXAMPLE, rounds, Unchanged), Ok(expected));
+    }
+
+    #[test]
+    fn test_worry_policies() {
+        assert_eq!(solve_stars(EXAMPLE, 20, DivideBy::new(3).unwrap()), 10605);
+        assert_eq!(DivideBy::new(0), None);
+        assert_eq!(DivideBy::new(7).map(|policy| policy.divisor()), Some(7));
+        let k = 3;
+        assert_eq!(solve_stars(EXAMPLE, 20, move |worry| worry / k), 10605);
+
+        // the same policy for every monkey is the same as the policy itself
+        let per_monkey = PerMonkey(
+            (0..4)
+                .map(|_| Box::new(DivideBy::new(3).unwrap()) as _)
+                .collect(),
+        );
+        assert_eq!(solve_stars(EXAMPLE, 20, per_monkey), 10605);
+        // without policies nothing changes
+        assert_eq!(
+            solve_stars(EXAMPLE, 1000, PerMonkey::default()),
+            5204 * 5192
+        );
+
+        // a cap doesn't keep the remainders, so the worry levels aren't reduced by
+        // 96577 but capped at 200000, and the example has levels between them
+        let history = simulate(EXAMPLE, 100, CapAt(200_000)).unwrap();
+        let levels = history
+            .rounds
+            .iter()
+            .flat_map(|round| round.items.iter().flatten());
+        assert!(levels.clone().all(|&level| level <= 200_000));
+        assert!(levels.clone().any(|&level| level > 96577));
+
+        let mixed = PerMonkey(vec![
+            Box::new(DivideBy::new(3).unwrap()),
+            Box::new(CapAt(70)),
+        ]);
+        let history = simulate(EXAMPLE, 1, mixed).unwrap();
+        // monkey 1 adds 6 to 54, 65, 75 and 74, caps them at 70 and throws them to 0
+        assert_eq!(history.rounds[0].items[0], [60, 70, 70, 70]);
+    }
+
+    #[test]
+    fn test_top_product() {
+        assert_eq!(top_product(&[3, 9, 1, 4], 2), Some(36));
+        assert_eq!(top_product(&[3, 9, 1, 4], 3), Some(108));
+        assert_eq!(top_product(&[3, 9, 1, 4], 0), Some(1));
+        assert_eq!(top_product(&[3, 9, 1, 4], 10), Some(108));
+        let counts = [u64::MAX; 8];
+        assert_eq!(
+            top_product(&counts, 2),
+            Some(u64::MAX as u128 * u64::MAX as u128)
+        );
+        assert_eq!(top_product(&counts, 8), None);
+        // the input has 8 monkeys, 10^12 rounds are too many for all of them
+        let counts = fast_forward(&load_input(11), 1_000_000_000_000, Unchanged);
+        assert_eq!(top_product(&counts.unwrap(), 8), None);
+        assert_eq!(
+            solve_stars_top(EXAMPLE, 20, DivideBy::new(3).unwrap(), 3),
+            Ok(101 * 95 * 105)
+        );
+    }
 }
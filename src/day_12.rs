@@ -1,8 +1,9 @@
 #![doc = include_str!("descriptions/day_12.md")]
 
-use std::collections::{HashMap, VecDeque};
-use std::{thread, usize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::{Arc, Mutex};
+use std::thread;
 
 pub const EXAMPLE: &str = "\
 Sabqponm
@@ -11,108 +12,338 @@ accszExk
 acctuvwj
 abdefghi";
 
-fn parse_input(input: &str) -> (Vec<Vec<u8>>, (usize, usize), (usize, usize)) {
+type Coord = (usize, usize);
+
+/// The squares that can be reached from `S` when `E` can't be reached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// the squares as `(x, y)`, sorted by row and then column
+    pub squares: Vec<Coord>,
+    /// the highest level that can be reached, like `'p'`
+    pub highest: char,
+    /// the square closest to `E`, counted in steps along the axes
+    pub closest: Coord,
+    /// the map with `.` for every square that can't be reached, except `E`
+    pub map: String,
+}
+
+impl Region {
+    /// Walks everything the rules allow from the start
+    fn explore(map: &[Vec<u8>], start: Coord, end: Coord, rules: &ClimbRules) -> Self {
+        let height = map.len() as i64;
+        let width = map[0].len() as i64;
+        let mut reached = vec![vec![false; map[0].len()]; map.len()];
+        reached[start.1][start.0] = true;
+        let mut stack = vec![start];
+        while let Some(current) = stack.pop() {
+            for &(dx, dy) in &rules.directions {
+                let (x, y) = (current.0 as i64 + dx as i64, current.1 as i64 + dy as i64);
+                if x < 0 || y < 0 || x >= width || y >= height {
+                    continue;
+                }
+                let (x, y) = (x as usize, y as usize);
+                if !reached[y][x] && rules.cost(map[current.1][current.0], map[y][x]).is_some() {
+                    reached[y][x] = true;
+                    stack.push((x, y));
+                }
+            }
+        }
+
+        let mut squares = Vec::new();
+        let mut rendered = String::new();
+        for (y, row) in map.iter().enumerate() {
+            for (x, &level) in row.iter().enumerate() {
+                rendered.push(match (x, y) {
+                    position if position == start => 'S',
+                    position if position == end => 'E',
+                    _ if reached[y][x] => level as char,
+                    _ => '.',
+                });
+                if reached[y][x] {
+                    squares.push((x, y));
+                }
+            }
+            rendered.push('\n');
+        }
+        let distance = |&(x, y): &Coord| x.abs_diff(end.0) + y.abs_diff(end.1);
+        Self {
+            highest: squares
+                .iter()
+                .map(|&(x, y)| map[y][x])
+                .max()
+                .unwrap_or(b'a') as char,
+            closest: *squares
+                .iter()
+                .min_by_key(|square| distance(square))
+                .unwrap(),
+            squares,
+            map: rendered,
+        }
+    }
+}
+
+/// Why the map can't be read or climbed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    MissingStart,
+    MissingEnd,
+    /// a second `S` or `E`, with the 1-based line and column
+    Duplicate {
+        character: char,
+        line: usize,
+        column: usize,
+    },
+    /// a row with another width than the first row
+    RaggedRow {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// a character that isn't a height from `a` to `z`, `S` or `E`
+    InvalidCharacter {
+        character: char,
+        line: usize,
+        column: usize,
+    },
+    /// `E` can't be reached, with everything that can be reached from `S`
+    Unreachable(Region),
+}
+
+impl std::fmt::Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::MissingStart => write!(f, "the start has to be marked with an 'S'"),
+            MapError::MissingEnd => write!(f, "the end has to be marked with an 'E'"),
+            MapError::Duplicate {
+                character,
+                line,
+                column,
+            } => write!(f, "line {line}, column {column}: a second '{character}'"),
+            MapError::RaggedRow {
+                line,
+                width,
+                expected,
+            } => write!(f, "line {line}: the row is {width} wide instead of {expected}"),
+            MapError::InvalidCharacter {
+                character,
+                line,
+                column,
+            } => write!(f, "line {line}, column {column}: invalid height '{character}'"),
+            MapError::Unreachable(region) => write!(
+                f,
+                "the end can't be reached, {} squares up to '{}' can be reached and ({}, {}) is the closest",
+                region.squares.len(),
+                region.highest,
+                region.closest.0,
+                region.closest.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+fn parse_input(input: &str) -> Result<(Vec<Vec<u8>>, Coord, Coord), MapError> {
     let mut y_vec: Vec<Vec<u8>> = Vec::new();
     let mut start: Option<(usize, usize)> = None;
     let mut end: Option<(usize, usize)> = None;
-    let mut c: char;
 
     for (y, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
         let mut x_vec = Vec::new();
-        for (x, ch) in line.chars().enumerate() {
-            c = ch;
-            if c == 'S' {
-                c = 'a';
-                start = Some((x, y));
-            } else if c == 'E' {
-                c = 'z';
-                end = Some((x, y));
+        for (x, c) in line.chars().enumerate() {
+            let (marker, height) = match c {
+                'S' => (&mut start, b'a'),
+                'E' => (&mut end, b'z'),
+                'a'..='z' => {
+                    x_vec.push(c as u8);
+                    continue;
+                }
+                _ => {
+                    return Err(MapError::InvalidCharacter {
+                        character: c,
+                        line: y + 1,
+                        column: x + 1,
+                    })
+                }
+            };
+            if marker.replace((x, y)).is_some() {
+                return Err(MapError::Duplicate {
+                    character: c,
+                    line: y + 1,
+                    column: x + 1,
+                });
+            }
+            x_vec.push(height);
+        }
+        if let Some(first) = y_vec.first() {
+            if first.len() != x_vec.len() {
+                return Err(MapError::RaggedRow {
+                    line: y + 1,
+                    width: x_vec.len(),
+                    expected: first.len(),
+                });
             }
-
-            x_vec.push(c as u8);
         }
         y_vec.push(x_vec);
     }
-    let start = start.expect("the Start has to be defined by an 'S'");
-    let end = end.expect("the End has to be defined by an 'E'");
-    (y_vec, start, end)
+    let start = start.ok_or(MapError::MissingStart)?;
+    let end = end.ok_or(MapError::MissingEnd)?;
+    Ok((y_vec, start, end))
 }
 
-fn get_path(
-    map: &Vec<Vec<u8>>,
-    start: &(usize, usize),
-    end: &(usize, usize),
-) -> Result<Vec<(usize, usize)>, ()> {
-    let height = map.len();
-    let width = map[0].len();
-    let directions: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-    let mut dequeue: VecDeque<(usize, usize)> = VecDeque::new();
-    dequeue.push_back(start.clone());
-    let mut visitied: HashMap<(usize, usize), Option<(usize, usize)>> = HashMap::new();
-    visitied.insert(start.clone(), None);
-
-    let mut cur_height: u8;
-    let mut neig_heigt: u8;
-    let mut next_x: i32;
-    let mut next_y: i32;
-    let mut next_pos: (usize, usize);
-
-    while let Some((cur_x, cur_y)) = dequeue.pop_front() {
-        cur_height = map[cur_y as usize][cur_x as usize];
+/// The rules for moving over the map. The default is the puzzle: a step goes to
+/// one of the four neighbors, at most one level up and any amount down, and costs 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClimbRules {
+    /// the steps as `(x, y)`, with `y` going down
+    pub directions: Vec<(i32, i32)>,
+    /// how many levels a step can go up at most
+    pub max_climb: u8,
+    /// how many levels a step can go down at most, `None` for any amount
+    pub max_descent: Option<u8>,
+    /// the cost of every step
+    pub step_cost: u32,
+    /// the extra cost for every level a step goes up
+    pub climb_cost: u32,
+}
+
+impl Default for ClimbRules {
+    fn default() -> Self {
+        Self {
+            directions: vec![(-1, 0), (1, 0), (0, -1), (0, 1)],
+            max_climb: 1,
+            max_descent: None,
+            step_cost: 1,
+            climb_cost: 0,
+        }
+    }
+}
+
+impl ClimbRules {
+    /// the puzzle rules, but going down is limited like going up
+    pub fn symmetric(limit: u8) -> Self {
+        Self {
+            max_climb: limit,
+            max_descent: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    /// adds the four diagonals to the directions
+    pub fn with_diagonals(mut self) -> Self {
+        self.directions.extend([(-1, -1), (1, -1), (-1, 1), (1, 1)]);
+        self
+    }
+
+    /// makes every level a step goes up cost extra
+    pub fn with_climb_cost(mut self, climb_cost: u32) -> Self {
+        self.climb_cost = climb_cost;
+        self
+    }
+
+    /// The cost of a step from the height `from` to `to`, `None` if it isn't allowed
+    pub fn cost(&self, from: u8, to: u8) -> Option<u32> {
+        if to > from {
+            (to - from <= self.max_climb)
+                .then(|| self.step_cost + self.climb_cost * (to - from) as u32)
+        } else {
+            self.max_descent
+                .is_none_or(|limit| from - to <= limit)
+                .then_some(self.step_cost)
+        }
+    }
+}
+
+/// The cheapest path from `start` to `end` with Dijkstra, as the total cost and
+/// the squares from the end back to the start
+fn find_path(
+    map: &[Vec<u8>],
+    start: Coord,
+    end: Coord,
+    rules: &ClimbRules,
+) -> Option<(u32, Vec<Coord>)> {
+    let height = map.len() as i64;
+    let width = map.first().map_or(0, |row| row.len()) as i64;
+
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((0, start)));
+    let mut costs: HashMap<Coord, u32> = HashMap::from([(start, 0)]);
+    let mut previous: HashMap<Coord, Coord> = HashMap::new();
+
+    while let Some(Reverse((cost, current))) = heap.pop() {
+        if costs.get(&current).is_some_and(|&best| best < cost) {
+            continue;
+        }
 
         // target reached?
-        if cur_x == end.0 && cur_y == end.1 {
-            let mut pre_node = (cur_x, cur_y);
-            let mut path: Vec<(usize, usize)> = Vec::new();
-            while let Some(next_node) = visitied.get(&pre_node) {
-                path.push(pre_node);
-                match next_node {
-                    Some(next) => pre_node = *next,
-                    None => break,
-                }
+        if current == end {
+            let mut path = vec![end];
+            while let Some(&before) = previous.get(path.last().unwrap()) {
+                path.push(before);
             }
-            return Ok(path);
+            return Some((cost, path));
         }
 
         // looking for neighbors
-        for (dir_x, dir_y) in directions {
-            next_x = cur_x as i32 + dir_x;
-            next_y = cur_y as i32 + dir_y;
-
-            if next_x < 0 || next_y < 0 || next_x as usize >= width || next_y as usize >= height {
+        for &(dx, dy) in &rules.directions {
+            let (x, y) = (current.0 as i64 + dx as i64, current.1 as i64 + dy as i64);
+            if x < 0 || y < 0 || x >= width || y >= height {
                 continue;
             }
-
-            next_pos = (next_x as usize, next_y as usize);
-
-            if visitied.contains_key(&next_pos) {
+            let next = (x as usize, y as usize);
+            let Some(step) = rules.cost(map[current.1][current.0], map[next.1][next.0]) else {
                 continue;
-            }
-
-            neig_heigt = map[next_pos.1][next_pos.0];
-
-            if neig_heigt <= cur_height || neig_heigt == cur_height + 1 {
-                dequeue.push_back(next_pos);
-                visitied.insert(next_pos, Some((cur_x, cur_y)));
+            };
+            if costs.get(&next).is_none_or(|&best| cost + step < best) {
+                costs.insert(next, cost + step);
+                previous.insert(next, current);
+                heap.push(Reverse((cost + step, next)));
             }
         }
     }
 
-    Err(())
+    None
+}
+
+fn get_path(
+    map: &[Vec<u8>],
+    start: Coord,
+    end: Coord,
+    rules: &ClimbRules,
+) -> Result<(u32, Vec<Coord>), MapError> {
+    find_path(map, start, end, rules)
+        .ok_or_else(|| MapError::Unreachable(Region::explore(map, start, end, rules)))
+}
+
+/// The cost of the cheapest path from `S` to `E` with the rules
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_12::{cheapest_path, ClimbRules, EXAMPLE};
+///
+/// assert_eq!(cheapest_path(EXAMPLE, &ClimbRules::default()), Ok(31));
+/// assert_eq!(cheapest_path(EXAMPLE, &ClimbRules::default().with_diagonals()), Ok(27));
+/// ```
+pub fn cheapest_path(input: &str, rules: &ClimbRules) -> Result<u32, MapError> {
+    let (map, start, end) = parse_input(input)?;
+    get_path(&map, start, end, rules).map(|(cost, _)| cost)
+}
+
+/// The fewest steps from `S` to `E`
+pub fn try_solve_star_one(input: &str) -> Result<usize, MapError> {
+    let (map, start, end) = parse_input(input)?;
+    let (_, path) = get_path(&map, start, end, &ClimbRules::default())?;
+    Ok(path.len() - 1)
 }
 
 pub fn solve_star_one(input: &str) -> usize {
-    let (map, start, end) = parse_input(input);
-    let path = get_path(&map, &start, &end);
-    if let Ok(path) = path {
-        return path.len() - 1;
-    }
-    return 0;
+    try_solve_star_one(input).unwrap_or_else(|error| panic!("{error}"))
 }
 
-pub fn solve_star_two(input: &str) -> usize {
-    let (map, _, end) = parse_input(input);
+/// The fewest steps from any square at level `a` to `E`. If there is no path, the
+/// error shows the region that can be reached from `S`.
+pub fn try_solve_star_two(input: &str) -> Result<usize, MapError> {
+    let (map, start, end) = parse_input(input)?;
     // get all a starter positions
     let mut starters: Vec<(usize, usize)> = Vec::new();
 
@@ -127,35 +358,49 @@ pub fn solve_star_two(input: &str) -> usize {
 
     // setup for concurrency
     let results = Arc::new(Mutex::new(Vec::new()));
-    let map = Arc::new(map); 
+    let map = Arc::new(map);
     let end = Arc::new(end);
     let mut handles = vec![];
-    
+
     // go threw every starter position
     for start in starters {
         let map_clone = Arc::clone(&map);
         let end_clone = Arc::clone(&end);
         let results_clone = Arc::clone(&results);
-        
+
         // alulate the path in the new thread
         let handle = thread::spawn(move || {
-            if let Ok(path) = get_path(&map_clone, &start, &end_clone) {
+            if let Some((_, path)) =
+                find_path(&map_clone, start, *end_clone, &ClimbRules::default())
+            {
                 let path_len = path.len() - 1;
                 let mut results = results_clone.lock().unwrap();
                 results.push(path_len);
             }
         });
-        
+
         handles.push(handle);
     }
-    
+
     // Wait for all threads to complete
     for handle in handles {
         handle.join().unwrap();
     }
 
     let results = results.lock().unwrap();
-    *results.iter().min().unwrap()
+    match results.iter().min() {
+        Some(&steps) => Ok(steps),
+        None => Err(MapError::Unreachable(Region::explore(
+            &map,
+            start,
+            *end,
+            &ClimbRules::default(),
+        ))),
+    }
+}
+
+pub fn solve_star_two(input: &str) -> usize {
+    try_solve_star_two(input).unwrap_or_else(|error| panic!("{error}"))
 }
 
 #[cfg(test)]
@@ -165,7 +410,7 @@ mod tests_day_12 {
 
     #[test]
     fn test_star_one_example() {
-        let result = solve_star_one(&EXAMPLE);
+        let result = solve_star_one(EXAMPLE);
         assert_eq!(result, 31);
     }
 
@@ -177,7 +422,7 @@ mod tests_day_12 {
 
     #[test]
     fn test_star_two_example() {
-        let result = solve_star_two(&EXAMPLE);
+        let result = solve_star_two(EXAMPLE);
         assert_eq!(result, 29);
     }
 
@@ -186,4 +431,110 @@ mod tests_day_12 {
         let result = solve_star_two(&load_input(12));
         assert_eq!(result, 465);
     }
+
+    #[test]
+    fn test_climb_rules() {
+        let rules = ClimbRules::default();
+        assert_eq!(rules.cost(b'a', b'b'), Some(1));
+        assert_eq!(rules.cost(b'a', b'c'), None);
+        assert_eq!(rules.cost(b'z', b'a'), Some(1));
+
+        let rules = ClimbRules::symmetric(2).with_climb_cost(10);
+        assert_eq!(rules.cost(b'a', b'c'), Some(21));
+        assert_eq!(rules.cost(b'd', b'b'), Some(1));
+        assert_eq!(rules.cost(b'd', b'a'), None);
+    }
+
+    #[test]
+    fn test_cheapest_path() {
+        let input = load_input(12);
+        assert_eq!(cheapest_path(&input, &ClimbRules::default()), Ok(472));
+
+        // the example climbs from `a` to `z` in single levels, so every path has
+        // to climb 25 levels
+        let weighted = ClimbRules::default().with_climb_cost(3);
+        assert_eq!(cheapest_path(EXAMPLE, &weighted), Ok(31 + 3 * 25));
+
+        // the shortest path of the example never goes down more than one level
+        assert_eq!(cheapest_path(EXAMPLE, &ClimbRules::symmetric(1)), Ok(31));
+        assert!(matches!(
+            cheapest_path("SbcdE", &ClimbRules::symmetric(1)),
+            Err(MapError::Unreachable(_))
+        ));
+
+        let steep = ClimbRules {
+            max_climb: 25,
+            ..ClimbRules::default()
+        };
+        assert_eq!(cheapest_path("Sz\nzE", &steep), Ok(2));
+        assert_eq!(cheapest_path("Sz\nzE", &steep.with_diagonals()), Ok(1));
+    }
+
+    #[test]
+    fn test_path_order() {
+        let (map, start, end) = parse_input(EXAMPLE).unwrap();
+        let (_, path) = get_path(&map, start, end, &ClimbRules::default()).unwrap();
+        assert_eq!(path.first(), Some(&end));
+        assert_eq!(path.last(), Some(&start));
+        assert_eq!(path.len(), 32);
+    }
+
+    #[test]
+    fn test_map_errors() {
+        assert_eq!(error_of("abc\nabE"), MapError::MissingStart);
+        assert_eq!(error_of("Sbc\nabc"), MapError::MissingEnd);
+        assert_eq!(
+            error_of("Sbc\nab\nabE"),
+            MapError::RaggedRow {
+                line: 2,
+                width: 2,
+                expected: 3
+            }
+        );
+        assert_eq!(
+            error_of("Sbc\naB E"),
+            MapError::InvalidCharacter {
+                character: 'B',
+                line: 2,
+                column: 2
+            }
+        );
+        assert_eq!(
+            error_of("SbE\nSbc"),
+            MapError::Duplicate {
+                character: 'S',
+                line: 2,
+                column: 1
+            }
+        );
+        let crlf = format!("S{}E\r\n{}\r\n", "bcdefghijklmnopqrstuvwxy", "a".repeat(26));
+        assert_eq!(try_solve_star_one(&crlf), Ok(25));
+    }
+
+    #[test]
+    fn test_unreachable_region() {
+        let input = "\
+Sbcdx
+abceE
+zzzzz";
+        let MapError::Unreachable(region) = error_of(input) else {
+            panic!("the end should be unreachable");
+        };
+        assert_eq!(region.squares.len(), 8);
+        assert_eq!(region.highest, 'e');
+        assert_eq!(region.closest, (3, 1));
+        assert_eq!(region.map, "Sbcd.\nabceE\n.....\n");
+        assert_eq!(
+            error_of(input).to_string(),
+            "the end can't be reached, 8 squares up to 'e' can be reached and (3, 1) is the closest"
+        );
+        assert!(matches!(
+            try_solve_star_two(input),
+            Err(MapError::Unreachable(_))
+        ));
+    }
+
+    fn error_of(input: &str) -> MapError {
+        try_solve_star_one(input).unwrap_err()
+    }
 }
@@ -1,5 +1,6 @@
 #![doc = include_str!("descriptions/day_13.md")]
 
+use std::fmt;
 use std::str::FromStr;
 
 pub fn solve_star_one(input: &str) -> usize {
@@ -53,63 +54,221 @@ pub fn solve_star_two(input: &str) -> usize {
         * (packets.iter().position(|e| *e == div1).unwrap() + 1)
 }
 
+/// A packet is a JSON value made of integers and lists.
+/// `Display` writes the canonical JSON form without any whitespace.
+///
+/// `==` and the order are the ones of the puzzle, where an integer is compared like
+/// a list with just that integer. So `1 == [1]` and `[[2]] == [2]` although they are
+/// different values, `same_structure` tells them apart.
+/// # Example
+/// ```
+/// use advent_of_code_2022::day_13::Packet;
+///
+/// let packet: Packet = "[ 1, [-2, []],\n 3 ]".parse().unwrap();
+/// assert_eq!(packet.to_string(), "[1,[-2,[]],3]");
+///
+/// let (one, list): (Packet, Packet) = ("1".parse().unwrap(), "[1]".parse().unwrap());
+/// assert!(one == list);
+/// assert!(!one.same_structure(&list));
+/// ```
 #[derive(Debug, Eq, Clone)]
-enum Packet {
+pub enum Packet {
     Integer(i32),
     Packet(Vec<Packet>),
 }
 
-impl FromStr for Packet {
-    type Err = &'static str;
+impl Packet {
+    /// `true` if both packets are the same JSON value, unlike `==` an integer is
+    /// never the same as a list
+    pub fn same_structure(&self, other: &Packet) -> bool {
+        match (self, other) {
+            (Packet::Integer(a), Packet::Integer(b)) => a == b,
+            (Packet::Packet(a), Packet::Packet(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_structure(b))
+            }
+            _ => false,
+        }
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // this is the final pack, that we want to return at the end
-        let mut packs: Vec<Packet> = Vec::new();
-        // holds the chars of one number before we parse it to an interger
-        let mut buf_string = String::with_capacity(2);
-
-        for c in s.chars() {
-            match c {
-                '[' => packs.push(Packet::Packet(Vec::new())),
-                ',' => {
-                    // extract the number if the buf_string isn't empty
-                    process_buffered_number(&mut packs, &mut buf_string);
-                }
-                ']' => {
-                    // there could be a number, check the buf_string for a number
-                    process_buffered_number(&mut packs, &mut buf_string);
-
-                    // now lets get the last vec to get it in propper
-                    let unpack = packs.pop().unwrap();
-                    // if the length is 0 we have finished the work
-                    if packs.len() == 0 {
-                        return Ok(unpack);
-                    }
+/// How deep lists can be nested in a packet, deeper packets are rejected instead of
+/// overflowing the stack while parsing, comparing or dropping them
+pub const MAX_DEPTH: usize = 256;
+
+/// Why a packet could not be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketError {
+    /// the 1-based line and column of the character that doesn't fit, for a missing
+    /// `]` the position of the `[` that isn't closed
+    pub line: usize,
+    pub column: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.reason
+        )
+    }
+}
+
+impl std::error::Error for PacketError {}
+
+/// A recursive descent parser for the JSON of a packet
+struct Parser<'a> {
+    bytes: &'a [u8],
+    /// the index of the next byte
+    position: usize,
+    /// how many lists are open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// the error at the byte with the index, the column counts characters
+    fn error(&self, position: usize, reason: &'static str) -> PacketError {
+        let before = &self.bytes[..position];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        PacketError {
+            line: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            // every character starts with a byte that isn't a UTF-8 continuation byte
+            column: before[line_start..]
+                .iter()
+                .filter(|&&b| b & 0xC0 != 0x80)
+                .count()
+                + 1,
+            reason,
+        }
+    }
+
+    /// skips the whitespace JSON allows and returns the next byte
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+        self.bytes.get(self.position).copied()
+    }
 
-                    // lets
-                    if let Packet::Packet(v) = packs.last_mut().unwrap() {
-                        v.push(unpack);
+    fn value(&mut self) -> Result<Packet, PacketError> {
+        match self.peek() {
+            Some(b'[') => self.list(),
+            Some(b'-' | b'0'..=b'9') => self.integer(),
+            Some(b']') => Err(self.error(self.position, "Unbalanced `]`")),
+            Some(b'"' | b'{' | b't' | b'f' | b'n') => {
+                Err(self.error(self.position, "Only integers and lists are supported"))
+            }
+            Some(_) => Err(self.error(self.position, "Unexpected character")),
+            None => Err(self.error(self.position, "Expected a value")),
+        }
+    }
+
+    fn list(&mut self) -> Result<Packet, PacketError> {
+        let open = self.position;
+        if self.depth == MAX_DEPTH {
+            return Err(self.error(open, "The lists are nested too deeply"));
+        }
+        self.depth += 1;
+        self.position += 1;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            self.depth -= 1;
+            return Ok(Packet::Packet(items));
+        }
+        loop {
+            if self.peek().is_none() {
+                return Err(self.error(open, "Missing `]`"));
+            }
+            items.push(self.value()?);
+            match self.peek() {
+                Some(b',') => {
+                    let comma = self.position;
+                    self.position += 1;
+                    if self.peek() == Some(b']') {
+                        return Err(self.error(comma, "Trailing `,`"));
                     }
                 }
-                _ => {
-                    buf_string.push(c);
+                Some(b']') => {
+                    self.position += 1;
+                    self.depth -= 1;
+                    return Ok(Packet::Packet(items));
                 }
+                Some(_) => return Err(self.error(self.position, "Expected `,` or `]`")),
+                None => return Err(self.error(open, "Missing `]`")),
             }
         }
+    }
 
-        Err("Can't convert string")
+    /// an integer with the JSON rules, so without a `+` or leading zeros
+    fn integer(&mut self) -> Result<Packet, PacketError> {
+        let start = self.position;
+        if self.bytes[self.position] == b'-' {
+            self.position += 1;
+        }
+        let digits = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(u8::is_ascii_digit)
+        {
+            self.position += 1;
+        }
+        if self.position == digits {
+            return Err(self.error(digits, "Expected a digit"));
+        }
+        if self.bytes[digits] == b'0' && self.position > digits + 1 {
+            return Err(self.error(digits, "Leading zeros aren't allowed"));
+        }
+        if let Some(b'.' | b'e' | b'E') = self.bytes.get(self.position) {
+            return Err(self.error(self.position, "Only integers are supported"));
+        }
+        // the bytes are ASCII, so they are valid UTF-8
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+        text.parse()
+            .map(Packet::Integer)
+            .map_err(|_| self.error(start, "The integer doesn't fit into 32 bits"))
     }
 }
 
-/// Used in `Packet::from_str(input)` for converting the buffered strin in to a number
-fn process_buffered_number(packs: &mut Vec<Packet>, buf_string: &mut String) {
-    if !buf_string.is_empty() {
-        let value = buf_string.parse::<i32>().unwrap();
+impl FromStr for Packet {
+    type Err = PacketError;
 
-        if let Packet::Packet(v) = packs.last_mut().unwrap() {
-            v.push(Packet::Integer(value));
+    /// Parses the JSON of a packet, with any whitespace between the values
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            bytes: s.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let packet = parser.value()?;
+        match parser.peek() {
+            None => Ok(packet),
+            Some(b']') => Err(parser.error(parser.position, "Unbalanced `]`")),
+            Some(_) => Err(parser.error(parser.position, "Unexpected character after the packet")),
+        }
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Integer(value) => write!(f, "{value}"),
+            Packet::Packet(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
         }
-        buf_string.clear();
     }
 }
 
@@ -135,24 +294,7 @@ impl PartialEq for Packet {
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (Self::Integer(l0), Self::Integer(r0)) => Some(l0.cmp(r0)),
-            (Self::Packet(_), Self::Integer(r0)) => {
-                Some(self.cmp(&Packet::Packet(vec![Packet::Integer(*r0)])))
-            }
-            (Self::Integer(l0), Self::Packet(_)) => {
-                Some(Packet::Packet(vec![Packet::Integer(*l0)]).cmp(other))
-            }
-            (Self::Packet(l0), Self::Packet(r0)) => {
-                for (a, b) in l0.iter().zip(r0.iter()) {
-                    match a.cmp(b) {
-                        std::cmp::Ordering::Equal => continue,
-                        ordering => return Some(ordering),
-                    }
-                }
-                return Some(l0.len().cmp(&r0.len()));
-            }
-        }
+        Some(self.cmp(other))
     }
 }
 
@@ -173,7 +315,7 @@ impl Ord for Packet {
                         ordering => return ordering,
                     }
                 }
-                return l0.len().cmp(&r0.len());
+                l0.len().cmp(&r0.len())
             }
         }
     }
@@ -208,6 +350,7 @@ pub const EXAMPLE: &str = "\
 mod tests_day_13 {
     use super::*;
     use crate::load_input;
+    use crate::rng::Rng;
 
     #[test]
     fn test_packet_from_str() {
@@ -279,11 +422,11 @@ mod tests_day_13 {
             }
 
             // Check second and third elements (integers)
-            for i in 1..3 {
-                if let Packet::Integer(val) = outer[i] {
-                    assert_eq!(val, 4);
+            for val in &outer[1..3] {
+                if let Packet::Integer(val) = val {
+                    assert_eq!(*val, 4);
                 } else {
-                    panic!("Expected Integer, got {:?}", outer[i]);
+                    panic!("Expected Integer, got {:?}", val);
                 }
             }
         } else {
@@ -310,30 +453,30 @@ mod tests_day_13 {
         let packet1 = Packet::from_str("[1,1,3,1,1]").unwrap();
         let packet2 = Packet::from_str("[1,1,5,1,1]").unwrap();
 
-        assert_eq![packet1 < packet2, true];
-        assert_eq![packet1 > packet2, false];
+        assert![packet1 < packet2];
+        assert![packet1 <= packet2];
 
         let packet1 = Packet::from_str("[[1],[2,3,4]]").unwrap();
         let packet2 = Packet::from_str("[[1],4]").unwrap();
 
-        assert_eq![packet1 < packet2, true];
-        assert_eq![packet1 > packet2, false];
+        assert![packet1 < packet2];
+        assert![packet1 <= packet2];
 
         let packet1 = Packet::from_str("[[[]]]").unwrap();
         let packet2 = Packet::from_str("[[]]").unwrap();
 
-        assert_eq![packet1 < packet2, false];
+        assert![packet1 >= packet2];
 
         // [9] vs [[8,7,6]]
         let packet1 = Packet::from_str("[9]").unwrap();
         let packet2 = Packet::from_str("[[8,7,6]]").unwrap();
 
-        assert_eq![packet1 < packet2, false]; // Hey AI! Here the test fails! Why?
+        assert![packet1 >= packet2]; // Hey AI! Here the test fails! Why?
 
         let packet1 = Packet::from_str("[1,[2,[3,[4,[5,6,7]]]],8,9]").unwrap();
         let packet2 = Packet::from_str("[1,[2,[3,[4,[5,6,0]]]],8,9]").unwrap();
 
-        assert_eq![packet1 < packet2, false];
+        assert![packet1 >= packet2];
     }
 
     #[test]
@@ -359,4 +502,134 @@ mod tests_day_13 {
         let sum = solve_star_two(&load_input(13));
         assert_eq!(sum, 21890);
     }
+
+    #[test]
+    fn test_packet_json() {
+        let packet = Packet::from_str(" [\t-1 ,[ ]\r\n, [-2147483648,0]] ").unwrap();
+        assert_eq!(packet.to_string(), "[-1,[],[-2147483648,0]]");
+        assert_eq!(Packet::from_str("42").unwrap().to_string(), "42");
+        assert_eq!(Packet::from_str("-0").unwrap().to_string(), "0");
+        assert!(Packet::from_str("[-1]").unwrap() < Packet::from_str("[0]").unwrap());
+    }
+
+    #[test]
+    fn test_packet_errors() {
+        let error = |s: &str| {
+            let error = Packet::from_str(s).unwrap_err();
+            assert_eq!(error.line, 1);
+            (error.column, error.reason)
+        };
+        assert_eq!(error("[1,[2,3]"), (1, "Missing `]`"));
+        assert_eq!(error("[1,[2,3"), (4, "Missing `]`"));
+        assert_eq!(error("[1,2]]"), (6, "Unbalanced `]`"));
+        assert_eq!(error("[1,]"), (3, "Trailing `,`"));
+        assert_eq!(error("[1, ]"), (3, "Trailing `,`"));
+        assert_eq!(error("[,1]"), (2, "Unexpected character"));
+        assert_eq!(error("[1 2]"), (4, "Expected `,` or `]`"));
+        assert_eq!(error("[1,x]"), (4, "Unexpected character"));
+        assert_eq!(
+            error("[1] [2]"),
+            (5, "Unexpected character after the packet")
+        );
+        assert_eq!(error("[01]"), (2, "Leading zeros aren't allowed"));
+        assert_eq!(error("[-]"), (3, "Expected a digit"));
+        assert_eq!(error("[1.5]"), (3, "Only integers are supported"));
+        assert_eq!(
+            error("[\"a\"]"),
+            (2, "Only integers and lists are supported")
+        );
+        assert_eq!(
+            error("[2147483648]"),
+            (2, "The integer doesn't fit into 32 bits")
+        );
+        assert_eq!(error("  "), (3, "Expected a value"));
+        assert_eq!(
+            Packet::from_str("[[]").unwrap_err().to_string(),
+            "line 1, column 1: Missing `]`"
+        );
+        // the position is counted in lines and characters, not in bytes
+        let error = Packet::from_str("[1,\n  [2],\n  \u{e4}]").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+        assert_eq!(error.reason, "Unexpected character");
+        let error = Packet::from_str("[\n[2]\n\n").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+    }
+
+    #[test]
+    fn test_packet_depth() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        let packet = Packet::from_str(&nested(MAX_DEPTH)).unwrap();
+        assert_eq!(packet.to_string(), nested(MAX_DEPTH));
+
+        // far too deep for the stack without the limit
+        let error = Packet::from_str(&nested(1_000_000)).unwrap_err();
+        assert_eq!(
+            (error.column, error.reason),
+            (MAX_DEPTH + 1, "The lists are nested too deeply")
+        );
+        let error = Packet::from_str(&"[".repeat(1_000_000)).unwrap_err();
+        assert_eq!(error.reason, "The lists are nested too deeply");
+    }
+
+    #[test]
+    fn test_packet_equality() {
+        let packet = |s: &str| Packet::from_str(s).unwrap();
+        assert_eq!(packet("1"), packet("[1]"));
+        assert_eq!(packet("[[2]]"), packet("[2]"));
+        assert_ne!(packet("[1]"), packet("[1,1]"));
+        assert!(!packet("1").same_structure(&packet("[1]")));
+        assert!(!packet("[[2]]").same_structure(&packet("[2]")));
+        assert!(packet("[[2],3]").same_structure(&packet("[ [2], 3 ]")));
+    }
+
+    #[test]
+    fn test_packet_round_trip_input() {
+        for line in load_input(13).lines().filter(|line| !line.is_empty()) {
+            assert_eq!(Packet::from_str(line).unwrap().to_string(), line.trim());
+        }
+    }
+
+    fn random_packet(rng: &mut Rng, depth: u32) -> Packet {
+        if depth == 0 || rng.below(3) == 0 {
+            let value = match rng.below(4) {
+                0 => i32::MIN as i64,
+                1 => i32::MAX as i64,
+                _ => rng.below(2001) as i64 - 1000,
+            };
+            return Packet::Integer(value as i32);
+        }
+        let length = rng.below(5);
+        Packet::Packet((0..length).map(|_| random_packet(rng, depth - 1)).collect())
+    }
+
+    /// adds random whitespace between the tokens of the JSON
+    fn spaced(rng: &mut Rng, json: &str) -> String {
+        let mut out = String::new();
+        for c in json.chars() {
+            if matches!(c, '[' | ']' | ',') {
+                for _ in 0..rng.below(3) {
+                    out.push([' ', '\t', '\n', '\r'][rng.below(4) as usize]);
+                }
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    #[test]
+    fn test_packet_round_trip_property() {
+        let mut rng = Rng::new(13);
+        for _ in 0..2000 {
+            let packet = random_packet(&mut rng, 5);
+            let json = packet.to_string();
+            let parsed = Packet::from_str(&json).unwrap();
+            assert!(packet.same_structure(&parsed), "{json}");
+            assert_eq!(parsed.to_string(), json);
+
+            let spaced = spaced(&mut rng, &json);
+            let parsed = Packet::from_str(&spaced).unwrap();
+            assert!(packet.same_structure(&parsed), "{spaced:?}");
+            assert_eq!(parsed.cmp(&packet), std::cmp::Ordering::Equal);
+        }
+    }
 }
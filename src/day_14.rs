@@ -10,7 +10,7 @@ fn parse_input(input: &str) -> Vec<Vec<(i32, i32)>> {
         .map(|line| {
             line.replace(" ", "") // replace all whitespaces
                 .split("->") // split the coordinates
-                .map(|s| parse_coord_string(s)) // convert to tuple
+                .map(parse_coord_string) // convert to tuple
                 .collect()
         })
         .collect()
@@ -115,7 +115,7 @@ impl Map {
 }
 
 pub fn solve_star_one(input: &str) -> usize {
-    let mut solid_map = Map::from_str(&input).expect("Coulnd parse Map");
+    let mut solid_map = Map::from_str(input).expect("Coulnd parse Map");
     let source: (i32, i32) = (500, 0);
     let mut count_sand = 0;
     let mut curr_sand = source;
@@ -134,7 +134,7 @@ pub fn solve_star_one(input: &str) -> usize {
 }
 
 pub fn solve_star_two(input: &str) -> usize {
-    let mut solid_map = Map::from_str(&input).expect("Coulnd parse Map");
+    let mut solid_map = Map::from_str(input).expect("Coulnd parse Map");
     solid_map.max_y += 1; // changed the max y value
     let source: (i32, i32) = (500, 0);
     let mut count_sand = 0;
@@ -16,6 +16,7 @@ pub mod day_11;
 pub mod day_12;
 pub mod day_13;
 pub mod day_14;
+mod rng;
 
 /// Loads the input by a given day
 /// # Example